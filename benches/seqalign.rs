@@ -1,11 +1,35 @@
 #![feature(test)]
 
+extern crate test;
+
 use test::{black_box, Bencher};
 
-use rand::{weak_rng, Rng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use seqalign::measures::{Levenshtein, LevenshteinDamerau, LCS};
 use seqalign::{Align, Measure};
 
+/// Measure wrapper that always uses dynamic programming.
+///
+/// The wrapper does not forward `Measure::fast_distance`, so that the
+/// dynamic programming and bit-parallel algorithms can be compared on
+/// the same measure.
+struct DynProg<M>(M);
+
+impl<M> Measure<char> for DynProg<M>
+where
+    M: Measure<char>,
+{
+    type Cost = M::Cost;
+
+    type Operation = M::Operation;
+
+    fn operations(&self) -> &[Self::Operation] {
+        self.0.operations()
+    }
+}
+
 static BENCH_ALPHABET: &[char] = &['a', 'b', 'c', 'd', 'e'];
 
 fn random_string<R>(rng: &mut R, len: usize) -> Vec<char>
@@ -13,7 +37,7 @@ where
     R: Rng,
 {
     (0..len)
-        .map(|_| rng.choose(BENCH_ALPHABET).unwrap())
+        .map(|_| BENCH_ALPHABET.choose(rng).unwrap())
         .cloned()
         .collect()
 }
@@ -22,8 +46,8 @@ fn random_pair<R>(rng: &mut R) -> (Vec<char>, Vec<char>)
 where
     R: Rng,
 {
-    let s1_len = rng.gen_range(0, 20);
-    let s2_len = rng.gen_range(0, 20);
+    let s1_len = rng.gen_range(0..20);
+    let s2_len = rng.gen_range(0..20);
 
     let s1 = random_string(rng, s1_len);
     let s2 = random_string(rng, s2_len);
@@ -33,7 +57,7 @@ where
 
 #[inline(never)]
 fn random_pairs(n: usize) -> Vec<(Vec<char>, Vec<char>)> {
-    let mut rng = weak_rng();
+    let mut rng = StdRng::seed_from_u64(42);
 
     let mut pairs = Vec::new();
     for _ in 0..n {
@@ -50,12 +74,25 @@ where
     let pairs = black_box(random_pairs(n));
 
     b.iter(move || {
-        for &(ref s1, ref s2) in &pairs {
+        for (s1, s2) in &pairs {
             black_box(measure.align(s1, s2));
         }
     })
 }
 
+fn distance_only_bench<M>(b: &mut Bencher, measure: M, n: usize)
+where
+    M: Measure<char>,
+{
+    let pairs = black_box(random_pairs(n));
+
+    b.iter(move || {
+        for (s1, s2) in &pairs {
+            black_box(measure.distance(s1, s2));
+        }
+    })
+}
+
 #[bench]
 fn lcs_distance_1000(b: &mut Bencher) {
     distance_bench(b, LCS::new(1, 1), 1000);
@@ -70,3 +107,13 @@ fn levenshtein_damerau_distance_1000(b: &mut Bencher) {
 fn levenshtein_distance_1000(b: &mut Bencher) {
     distance_bench(b, Levenshtein::new(1, 1, 1), 1000);
 }
+
+#[bench]
+fn levenshtein_bitparallel_distance_1000(b: &mut Bencher) {
+    distance_only_bench(b, Levenshtein::new(1, 1, 1), 1000);
+}
+
+#[bench]
+fn levenshtein_dp_distance_1000(b: &mut Bencher) {
+    distance_only_bench(b, DynProg(Levenshtein::new(1, 1, 1)), 1000);
+}
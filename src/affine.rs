@@ -0,0 +1,510 @@
+use crate::cost::Cost;
+use crate::equivalence::{Equal, Equivalence};
+use crate::op::{EditScript, IndexedOperation};
+use crate::{CostMatrix, SeqPair};
+
+/// Levenshtein distance with affine gap costs.
+///
+/// This measure uses the following operations:
+///
+/// * Insert, opening or extending a gap
+/// * Delete, opening or extending a gap
+/// * Substitute
+/// * Match
+///
+/// A gap of *k* insertions or deletions costs *gap_open + k ·
+/// gap_extend*, so that one long gap is cheaper than several short gaps.
+/// With a gap-open cost of zero, this measure is the same as Levenshtein
+/// distance. Elements match when they are equal according to `Eq`, use
+/// `AffineGap::with_equivalence` to match elements using another
+/// equivalence.
+///
+/// Affine gap costs cannot be computed with a single cost matrix. This
+/// measure is aligned using Gotoh's algorithm, which keeps three cost
+/// matrices: the costs of alignments that end in a match or substitution,
+/// a deletion, and an insertion. Consequently, this measure does not
+/// implement `Measure`, alignments are computed with `AffineGap::align`.
+#[derive(Clone, Debug)]
+pub struct AffineGap<C = usize, E = Equal> {
+    substitute_cost: C,
+    gap_open_cost: C,
+    gap_extend_cost: C,
+    equivalence: E,
+}
+
+/// Construct an affine gap measure with the associated substitution,
+/// gap-open, and gap-extend cost.
+impl AffineGap {
+    pub fn new(substitute_cost: usize, gap_open_cost: usize, gap_extend_cost: usize) -> Self {
+        Self::with_costs(substitute_cost, gap_open_cost, gap_extend_cost)
+    }
+}
+
+impl<C> AffineGap<C>
+where
+    C: Cost,
+{
+    /// Construct an affine gap measure with the associated substitution,
+    /// gap-open, and gap-extend cost of any cost type.
+    pub fn with_costs(substitute_cost: C, gap_open_cost: C, gap_extend_cost: C) -> Self {
+        AffineGap {
+            substitute_cost,
+            gap_open_cost,
+            gap_extend_cost,
+            equivalence: Equal,
+        }
+    }
+}
+
+impl<C, E> AffineGap<C, E>
+where
+    C: Cost,
+{
+    /// Use the given equivalence to decide which elements match.
+    ///
+    /// By default, elements match when they are equal according to `Eq`.
+    pub fn with_equivalence<F>(self, equivalence: F) -> AffineGap<C, F> {
+        AffineGap {
+            substitute_cost: self.substitute_cost,
+            gap_open_cost: self.gap_open_cost,
+            gap_extend_cost: self.gap_extend_cost,
+            equivalence,
+        }
+    }
+
+    /// Align two sequences.
+    pub fn align<'a, T>(&'a self, source: &'a [T], target: &'a [T]) -> AffineAlignment<'a, T, C, E>
+    where
+        E: Equivalence<T>,
+    {
+        let pair = SeqPair { source, target };
+        let rows = source.len() + 1;
+        let columns = target.len() + 1;
+
+        let mut matrices = GotohMatrices {
+            matches: CostMatrix::new(rows, columns),
+            deletes: CostMatrix::new(rows, columns),
+            inserts: CostMatrix::new(rows, columns),
+        };
+        self.fill(&pair, &mut matrices);
+
+        AffineAlignment {
+            measure: self,
+            pair,
+            matrices,
+        }
+    }
+
+    /// Compute the edit distance of two sequences.
+    ///
+    /// This method gives the same result as `align(source,
+    /// target).distance()`, but only keeps two rows of each cost matrix
+    /// in memory.
+    pub fn distance<T>(&self, source: &[T], target: &[T]) -> C
+    where
+        E: Equivalence<T>,
+    {
+        let pair = SeqPair { source, target };
+        let rows = source.len() + 1;
+        let columns = target.len() + 1;
+
+        let mut matrices = GotohMatrices {
+            matches: CostMatrix::with_stored_rows(rows, columns, 2),
+            deletes: CostMatrix::with_stored_rows(rows, columns, 2),
+            inserts: CostMatrix::with_stored_rows(rows, columns, 2),
+        };
+        self.fill(&pair, &mut matrices);
+
+        matrices.best_cost(source.len(), target.len())
+    }
+
+    /// Cost of opening a gap, including the cost of its first element.
+    fn gap_cost(&self) -> C {
+        self.gap_open_cost.saturating_add(self.gap_extend_cost)
+    }
+
+    fn fill<T>(&self, pair: &SeqPair<T>, matrices: &mut GotohMatrices<C>)
+    where
+        E: Equivalence<T>,
+    {
+        let (rows, columns) = matrices.matches.shape();
+
+        for source_idx in 0..rows {
+            for target_idx in 0..columns {
+                // Alignments of empty prefixes end in a match, so that
+                // the first gap is opened.
+                if source_idx == 0 && target_idx == 0 {
+                    matrices.matches[(0, 0)] = C::zero();
+                    matrices.deletes[(0, 0)] = C::max_value();
+                    matrices.inserts[(0, 0)] = C::max_value();
+                    continue;
+                }
+
+                matrices.matches[(source_idx, target_idx)] = if source_idx > 0 && target_idx > 0 {
+                    let cost = if self
+                        .equivalence
+                        .equivalent(&pair.source[source_idx - 1], &pair.target[target_idx - 1])
+                    {
+                        C::zero()
+                    } else {
+                        self.substitute_cost
+                    };
+                    matrices
+                        .best_cost(source_idx - 1, target_idx - 1)
+                        .saturating_add(cost)
+                } else {
+                    C::max_value()
+                };
+
+                matrices.deletes[(source_idx, target_idx)] = if source_idx > 0 {
+                    let open = matrices
+                        .best_cost(source_idx - 1, target_idx)
+                        .saturating_add(self.gap_cost());
+                    let extend = matrices.deletes[(source_idx - 1, target_idx)]
+                        .saturating_add(self.gap_extend_cost);
+                    open.min(extend)
+                } else {
+                    C::max_value()
+                };
+
+                matrices.inserts[(source_idx, target_idx)] = if target_idx > 0 {
+                    let open = matrices
+                        .best_cost(source_idx, target_idx - 1)
+                        .saturating_add(self.gap_cost());
+                    let extend = matrices.inserts[(source_idx, target_idx - 1)]
+                        .saturating_add(self.gap_extend_cost);
+                    open.min(extend)
+                } else {
+                    C::max_value()
+                };
+            }
+        }
+    }
+}
+
+/// Affine gap operation with associated cost.
+///
+/// The cost of an operation that opens a gap includes the cost of
+/// extending the gap with its first element.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum AffineGapOp<C = usize> {
+    InsertOpen(C),
+    InsertExtend(C),
+    DeleteOpen(C),
+    DeleteExtend(C),
+    Match,
+    Substitute(C),
+}
+
+/// The three cost matrices of Gotoh's algorithm.
+struct GotohMatrices<C> {
+    /// Costs of alignments that end in a match or substitution.
+    matches: CostMatrix<C>,
+
+    /// Costs of alignments that end in a deletion.
+    deletes: CostMatrix<C>,
+
+    /// Costs of alignments that end in an insertion.
+    inserts: CostMatrix<C>,
+}
+
+impl<C> GotohMatrices<C>
+where
+    C: Cost,
+{
+    fn best_cost(&self, source_idx: usize, target_idx: usize) -> C {
+        self.matches[(source_idx, target_idx)]
+            .min(self.deletes[(source_idx, target_idx)])
+            .min(self.inserts[(source_idx, target_idx)])
+    }
+
+    /// Get the state with the lowest cost in a cell, preferring matches
+    /// over deletions over insertions.
+    ///
+    /// Only states that can reach the cell are considered. Since costs
+    /// saturate, a state that cannot reach the cell may have the same
+    /// cost as the states that can.
+    fn best_state(&self, source_idx: usize, target_idx: usize) -> GotohState {
+        let states = [
+            (
+                GotohState::Match,
+                source_idx > 0 && target_idx > 0,
+                &self.matches,
+            ),
+            (GotohState::Delete, source_idx > 0, &self.deletes),
+            (GotohState::Insert, target_idx > 0, &self.inserts),
+        ];
+
+        states
+            .iter()
+            .filter(|&&(_, reachable, _)| reachable)
+            .min_by_key(|&&(_, _, costs)| costs[(source_idx, target_idx)])
+            .map(|&(state, _, _)| state)
+            .unwrap_or(GotohState::Match)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum GotohState {
+    Match,
+    Delete,
+    Insert,
+}
+
+/// Alignment with affine gap costs.
+pub struct AffineAlignment<'a, T, C = usize, E = Equal> {
+    measure: &'a AffineGap<C, E>,
+    pair: SeqPair<'a, T>,
+    matrices: GotohMatrices<C>,
+}
+
+impl<'a, T, C, E> AffineAlignment<'a, T, C, E>
+where
+    C: Cost,
+    E: Equivalence<T>,
+{
+    /// Get the edit distance.
+    pub fn distance(&self) -> C {
+        self.matrices
+            .best_cost(self.pair.source.len(), self.pair.target.len())
+    }
+
+    /// Return the script of edit operations to rewrite the source sequence
+    /// to the target sequence. If there are multiple possible edit scripts,
+    /// this method will return one of the possible edit scripts.
+    pub fn edit_script(&self) -> EditScript<AffineGapOp<C>> {
+        let measure = self.measure;
+        let matrices = &self.matrices;
+
+        let mut source_idx = self.pair.source.len();
+        let mut target_idx = self.pair.target.len();
+        let mut state = matrices.best_state(source_idx, target_idx);
+        let mut script = Vec::new();
+
+        while source_idx != 0 || target_idx != 0 {
+            let op = match state {
+                GotohState::Match => {
+                    source_idx -= 1;
+                    target_idx -= 1;
+                    state = matrices.best_state(source_idx, target_idx);
+
+                    if measure
+                        .equivalence
+                        .equivalent(&self.pair.source[source_idx], &self.pair.target[target_idx])
+                    {
+                        AffineGapOp::Match
+                    } else {
+                        AffineGapOp::Substitute(measure.substitute_cost)
+                    }
+                }
+                GotohState::Delete => {
+                    let cost = matrices.deletes[(source_idx, target_idx)];
+                    source_idx -= 1;
+
+                    if source_idx > 0
+                        && matrices.deletes[(source_idx, target_idx)]
+                            .saturating_add(measure.gap_extend_cost)
+                            == cost
+                    {
+                        AffineGapOp::DeleteExtend(measure.gap_extend_cost)
+                    } else {
+                        state = matrices.best_state(source_idx, target_idx);
+                        AffineGapOp::DeleteOpen(measure.gap_cost())
+                    }
+                }
+                GotohState::Insert => {
+                    let cost = matrices.inserts[(source_idx, target_idx)];
+                    target_idx -= 1;
+
+                    if target_idx > 0
+                        && matrices.inserts[(source_idx, target_idx)]
+                            .saturating_add(measure.gap_extend_cost)
+                            == cost
+                    {
+                        AffineGapOp::InsertExtend(measure.gap_extend_cost)
+                    } else {
+                        state = matrices.best_state(source_idx, target_idx);
+                        AffineGapOp::InsertOpen(measure.gap_cost())
+                    }
+                }
+            };
+
+            script.push(IndexedOperation::new(op, source_idx, target_idx));
+        }
+
+        script.reverse();
+
+        script
+    }
+
+    /// Get the costs of alignments that end in a match or substitution.
+    pub fn match_costs(&self) -> &CostMatrix<C> {
+        &self.matrices.matches
+    }
+
+    /// Get the costs of alignments that end in a deletion.
+    pub fn delete_costs(&self) -> &CostMatrix<C> {
+        &self.matrices.deletes
+    }
+
+    /// Get the costs of alignments that end in an insertion.
+    pub fn insert_costs(&self) -> &CostMatrix<C> {
+        &self.matrices.inserts
+    }
+
+    /// Get the sequence pair associated with this alignment.
+    pub fn seq_pair(&self) -> &SeqPair<'_, T> {
+        &self.pair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cost::OrderedFloat;
+    use crate::equivalence::EquivalenceFn;
+    use crate::measures::Levenshtein;
+    use crate::op::IndexedOperation;
+    use crate::Align;
+
+    use super::AffineGap;
+    use super::AffineGapOp::*;
+
+    lazy_static! {
+        static ref PAIRS: Vec<(Vec<char>, Vec<char>)> = vec![
+            ("", ""),
+            ("pineapple", ""),
+            ("", "pineapple"),
+            ("pineapple", "pen"),
+            ("pen", "pineapple"),
+            ("pineapple", "applet"),
+            ("tpyo", "typo"),
+        ]
+        .into_iter()
+        .map(|(source, target)| (source.chars().collect(), target.chars().collect()))
+        .collect();
+    }
+
+    #[test]
+    fn affine_gap_levenshtein_test() {
+        // Without gap-open costs, affine gap costs are linear.
+        let affine = AffineGap::new(1, 0, 1);
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        for (source, target) in PAIRS.iter() {
+            let alignment = affine.align(source, target);
+            assert_eq!(
+                alignment.distance(),
+                levenshtein.align(source, target).distance()
+            );
+            assert_eq!(affine.distance(source, target), alignment.distance());
+        }
+    }
+
+    #[test]
+    fn affine_gap_equivalence_test() {
+        let source: Vec<char> = "PineApple".chars().collect();
+        let target: Vec<char> = "pineapple".chars().collect();
+
+        let affine = AffineGap::new(1, 2, 1);
+        assert_eq!(affine.distance(&source, &target), 2);
+
+        let case_insensitive = affine.with_equivalence(EquivalenceFn::new(|a: &char, b: &char| {
+            a.eq_ignore_ascii_case(b)
+        }));
+        let alignment = case_insensitive.align(&source, &target);
+        assert_eq!(alignment.distance(), 0);
+        assert!(alignment
+            .edit_script()
+            .iter()
+            .all(|op| *op.operation() == Match));
+    }
+
+    #[test]
+    fn affine_gap_test() {
+        let affine = AffineGap::new(1, 2, 1);
+
+        for (source, target) in PAIRS.iter() {
+            let alignment = affine.align(source, target);
+            assert_eq!(affine.distance(source, target), alignment.distance());
+
+            // The cost of the edit script is the edit distance.
+            let script_cost = alignment
+                .edit_script()
+                .iter()
+                .map(|op| match *op.operation() {
+                    InsertOpen(cost) | InsertExtend(cost) | DeleteOpen(cost)
+                    | DeleteExtend(cost) | Substitute(cost) => cost,
+                    Match => 0,
+                })
+                .sum::<usize>();
+            assert_eq!(script_cost, alignment.distance());
+        }
+
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+        let apple: Vec<char> = "apple".chars().collect();
+
+        // One gap of four deletions.
+        let alignment = affine.align(&pineapple, &apple);
+        assert_eq!(alignment.distance(), 6);
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(DeleteOpen(3), 0, 0),
+                IndexedOperation::new(DeleteExtend(1), 1, 0),
+                IndexedOperation::new(DeleteExtend(1), 2, 0),
+                IndexedOperation::new(DeleteExtend(1), 3, 0),
+                IndexedOperation::new(Match, 4, 0),
+                IndexedOperation::new(Match, 5, 1),
+                IndexedOperation::new(Match, 6, 2),
+                IndexedOperation::new(Match, 7, 3),
+                IndexedOperation::new(Match, 8, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn affine_gap_prefers_long_gaps_test() {
+        let source: Vec<char> = "abcdef".chars().collect();
+        let target: Vec<char> = "af".chars().collect();
+
+        let affine =
+            AffineGap::with_costs(OrderedFloat(2.0f64), OrderedFloat(3.), OrderedFloat(0.5));
+        let alignment = affine.align(&source, &target);
+        assert_eq!(alignment.distance(), OrderedFloat(5.));
+
+        let n_gaps = alignment
+            .edit_script()
+            .iter()
+            .filter(|op| match op.operation() {
+                DeleteOpen(_) => true,
+                _ => false,
+            })
+            .count();
+        assert_eq!(n_gaps, 1);
+    }
+
+    #[test]
+    fn affine_gap_saturated_costs_test() {
+        // All states saturate in the first column, traceback should only
+        // consider deletions there.
+        let affine = AffineGap::with_costs(1u8, 200, 100);
+        let alignment = affine.align(&['a', 'b'], &[]);
+        assert_eq!(alignment.distance(), u8::max_value());
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(DeleteOpen(u8::max_value()), 0, 0),
+                IndexedOperation::new(DeleteExtend(100), 1, 0),
+            ]
+        );
+
+        let alignment = affine.align(&[], &['a', 'b']);
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(InsertOpen(u8::max_value()), 0, 0),
+                IndexedOperation::new(InsertExtend(100), 0, 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,430 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::mem;
+
+use crate::dynprog::fill_cost_matrix;
+use crate::error::AlignError;
+use crate::op::{BestCost, EditScript, IndexedOperation};
+use crate::{Alignment, AlignmentMode, CostMatrix, Measure, SeqPair};
+
+/// Reusable aligner.
+///
+/// `Align::align` allocates a new cost matrix for every alignment and
+/// returns an `Alignment` that borrows the measure and the sequences. An
+/// `Aligner` owns the measure and a cost matrix buffer that is reused
+/// between alignments. The buffer only grows when a pair of sequences is
+/// aligned that is larger than all earlier pairs. Alignments are returned
+/// as owned `AlignmentResult`s.
+///
+/// ```
+/// use seqalign::Aligner;
+/// use seqalign::measures::Levenshtein;
+///
+/// let mut aligner = Aligner::new(Levenshtein::new(1, 1, 1));
+///
+/// let results: Vec<_> = [("applet", "apple"), ("pen", "pineapple")]
+///     .iter()
+///     .map(|(source, target)| {
+///         let source: Vec<char> = source.chars().collect();
+///         let target: Vec<char> = target.chars().collect();
+///         aligner.align(&source, &target)
+///     })
+///     .collect();
+///
+/// assert_eq!(results[0].distance(), 1);
+/// assert_eq!(results[1].distance(), 7);
+/// ```
+pub struct Aligner<M, S, U = S>
+where
+    M: Measure<S, U>,
+{
+    measure: M,
+    cost_matrix: CostMatrix<M::Cost>,
+    _phantom: PhantomData<fn(&S, &U)>,
+}
+
+impl<M, S, U> Aligner<M, S, U>
+where
+    M: Measure<S, U>,
+{
+    /// Construct an aligner for the given measure.
+    pub fn new(measure: M) -> Self {
+        Aligner {
+            measure,
+            cost_matrix: CostMatrix::new(0, 0),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Align two sequences.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix or when it is not possible to backtrack.
+    /// Use `try_align` to get an error instead.
+    pub fn align(&mut self, source: &[S], target: &[U]) -> AlignmentResult<M::Operation, M::Cost> {
+        self.try_align(source, target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Align two sequences.
+    ///
+    /// This method returns an error when none of the measure's operations
+    /// can be applied to a cell of the cost matrix or when it is not
+    /// possible to backtrack.
+    pub fn try_align(
+        &mut self,
+        source: &[S],
+        target: &[U],
+    ) -> Result<AlignmentResult<M::Operation, M::Cost>, AlignError> {
+        let pair = SeqPair { source, target };
+        self.fill(&pair)?;
+        backtrack(&self.measure, pair, &mut self.cost_matrix)
+    }
+
+    /// Compute the edit distance of two sequences.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix.
+    pub fn distance(&mut self, source: &[S], target: &[U]) -> M::Cost {
+        let pair = SeqPair { source, target };
+        self.fill(&pair).unwrap_or_else(|err| panic!("{}", err));
+        self.cost_matrix[(source.len(), target.len())]
+    }
+
+    /// Get the measure.
+    pub fn measure(&self) -> &M {
+        &self.measure
+    }
+
+    fn fill(&mut self, pair: &SeqPair<S, U>) -> Result<(), AlignError> {
+        self.cost_matrix
+            .reset(pair.source.len() + 1, pair.target.len() + 1);
+        fill_cost_matrix(
+            &self.measure,
+            pair,
+            &mut self.cost_matrix,
+            AlignmentMode::global(),
+        )
+    }
+}
+
+/// Aligner for one source and many targets with shared prefixes.
+///
+/// The cost of a cell of the cost matrix only depends on the prefixes of
+/// the source and the target up to that cell. When a source sequence is
+/// aligned with a target that shares a prefix with the previous target,
+/// the columns of the cost matrix for that prefix are reused and only the
+/// remaining columns are computed. This makes it efficient to align a
+/// source with a sorted list of targets, such as a dictionary, or with
+/// the words of a trie in depth-first order.
+///
+/// ```
+/// use seqalign::PrefixAligner;
+/// use seqalign::measures::Levenshtein;
+///
+/// let source: Vec<char> = "aple".chars().collect();
+/// let mut aligner = PrefixAligner::new(Levenshtein::new(1, 1, 1), &source);
+///
+/// let distances: Vec<_> = ["apple", "applet", "apply", "maple"]
+///     .iter()
+///     .map(|target| aligner.distance(&target.chars().collect::<Vec<_>>()))
+///     .collect();
+///
+/// assert_eq!(distances, vec![1, 2, 2, 1]);
+/// ```
+pub struct PrefixAligner<'a, M, S, U = S>
+where
+    M: Measure<S, U>,
+{
+    measure: M,
+    source: &'a [S],
+    target: Vec<U>,
+    cost_matrix: CostMatrix<M::Cost>,
+    filled_columns: usize,
+}
+
+impl<'a, M, S, U> PrefixAligner<'a, M, S, U>
+where
+    M: Measure<S, U>,
+    U: Clone + Eq,
+{
+    /// Construct an aligner for the given measure and source sequence.
+    pub fn new(measure: M, source: &'a [S]) -> Self {
+        PrefixAligner {
+            measure,
+            source,
+            target: Vec::new(),
+            cost_matrix: CostMatrix::new(source.len() + 1, 1),
+            filled_columns: 0,
+        }
+    }
+
+    /// Align the source sequence with a target sequence.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix or when it is not possible to backtrack.
+    /// Use `try_align` to get an error instead.
+    pub fn align(&mut self, target: &[U]) -> AlignmentResult<M::Operation, M::Cost> {
+        self.try_align(target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Align the source sequence with a target sequence.
+    ///
+    /// This method returns an error when none of the measure's operations
+    /// can be applied to a cell of the cost matrix or when it is not
+    /// possible to backtrack.
+    pub fn try_align(
+        &mut self,
+        target: &[U],
+    ) -> Result<AlignmentResult<M::Operation, M::Cost>, AlignError> {
+        self.set_target(target);
+        self.fill()?;
+
+        let pair = SeqPair {
+            source: self.source,
+            target: &self.target,
+        };
+        backtrack(&self.measure, pair, &mut self.cost_matrix)
+    }
+
+    /// Compute the edit distance of the source sequence and a target
+    /// sequence.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix.
+    pub fn distance(&mut self, target: &[U]) -> M::Cost {
+        self.set_target(target);
+        self.fill().unwrap_or_else(|err| panic!("{}", err));
+        self.cost_matrix[(self.source.len(), self.target.len())]
+    }
+
+    /// Get the measure.
+    pub fn measure(&self) -> &M {
+        &self.measure
+    }
+
+    /// Get the source sequence.
+    pub fn source(&self) -> &[S] {
+        self.source
+    }
+
+    /// Fill the columns of the cost matrix that are not filled yet.
+    fn fill(&mut self) -> Result<(), AlignError> {
+        self.measure.check_sequences(self.source, &self.target)?;
+
+        let columns = self.target.len() + 1;
+        self.cost_matrix.resize_columns(columns);
+
+        let pair = SeqPair {
+            source: self.source,
+            target: &self.target,
+        };
+
+        for source_idx in 0..self.cost_matrix.rows() {
+            for target_idx in self.filled_columns..columns {
+                if source_idx == 0 && target_idx == 0 {
+                    continue;
+                }
+
+                self.cost_matrix[(source_idx, target_idx)] = self
+                    .measure
+                    .best_cost(&pair, &self.cost_matrix, source_idx, target_idx)
+                    .ok_or(AlignError::NoApplicableOperation {
+                        source_idx,
+                        target_idx,
+                    })?;
+            }
+        }
+
+        self.filled_columns = columns;
+
+        Ok(())
+    }
+
+    /// Replace the target, only invalidating the columns of the cost
+    /// matrix after the common prefix of the old and new target.
+    fn set_target(&mut self, target: &[U]) {
+        let prefix_len = self
+            .target
+            .iter()
+            .zip(target)
+            .take_while(|(old, new)| old == new)
+            .count();
+
+        self.target.truncate(prefix_len);
+        self.target.extend_from_slice(&target[prefix_len..]);
+        self.filled_columns = self.filled_columns.min(prefix_len + 1);
+    }
+}
+
+/// Backtrack to get an owned alignment result.
+fn backtrack<M, S, U>(
+    measure: &M,
+    pair: SeqPair<S, U>,
+    cost_matrix: &mut CostMatrix<M::Cost>,
+) -> Result<AlignmentResult<M::Operation, M::Cost>, AlignError>
+where
+    M: Measure<S, U>,
+{
+    // Lend the cost matrix to an alignment for backtracking.
+    let alignment = Alignment {
+        measure,
+        pair,
+        cost_matrix: mem::replace(cost_matrix, CostMatrix::new(0, 0)),
+        mode: AlignmentMode::global(),
+        max_distance: None,
+    };
+
+    let result = alignment
+        .try_edit_script()
+        .map(|edit_script| AlignmentResult {
+            distance: alignment.distance(),
+            edit_script,
+        });
+
+    *cost_matrix = alignment.cost_matrix;
+
+    result
+}
+
+/// Owned alignment result.
+///
+/// In contrast to `Alignment`, this type does not borrow the measure or
+/// the aligned sequences.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct AlignmentResult<O, C = usize>
+where
+    O: Debug,
+{
+    distance: C,
+    edit_script: EditScript<O>,
+}
+
+impl<O, C> AlignmentResult<O, C>
+where
+    O: Debug,
+    C: Copy,
+{
+    /// Get the edit distance.
+    pub fn distance(&self) -> C {
+        self.distance
+    }
+
+    /// Get the edit script.
+    pub fn edit_script(&self) -> &[IndexedOperation<O>] {
+        &self.edit_script
+    }
+
+    /// Get the edit script, consuming the result.
+    pub fn into_edit_script(self) -> EditScript<O> {
+        self.edit_script
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::measures::{Levenshtein, LevenshteinDamerau};
+    use crate::{Align, Measure};
+
+    use super::{Aligner, AlignmentResult, PrefixAligner};
+
+    static PAIRS: &[(&str, &str)] = &[
+        ("pineapple", "pen"),
+        ("", ""),
+        ("pen", "pineapple"),
+        ("pineapple", "applet"),
+        ("tpyo", "typo"),
+        ("", "pineapple"),
+    ];
+
+    fn check_aligner<M>(measure: M)
+    where
+        M: Clone + Measure<char>,
+    {
+        let mut aligner = Aligner::new(measure.clone());
+
+        for &(source, target) in PAIRS {
+            let source: Vec<char> = source.chars().collect();
+            let target: Vec<char> = target.chars().collect();
+
+            let alignment = measure.align(&source, &target);
+            assert_eq!(
+                aligner.align(&source, &target),
+                AlignmentResult {
+                    distance: alignment.distance(),
+                    edit_script: alignment.edit_script(),
+                }
+            );
+            assert_eq!(aligner.distance(&source, &target), alignment.distance());
+        }
+    }
+
+    #[test]
+    fn aligner_test() {
+        check_aligner(Levenshtein::new(1, 1, 1));
+        check_aligner(LevenshteinDamerau::new(1, 1, 1, 1));
+    }
+
+    #[test]
+    fn aligner_result_is_owned_test() {
+        let mut aligner = Aligner::new(Levenshtein::new(1, 1, 1));
+
+        let result = {
+            let source: Vec<char> = "applet".chars().collect();
+            let target: Vec<char> = "apple".chars().collect();
+            aligner.align(&source, &target)
+        };
+
+        assert_eq!(result.distance(), 1);
+        assert_eq!(result.into_edit_script().len(), 6);
+    }
+
+    #[test]
+    fn prefix_aligner_test() {
+        let mut targets: Vec<Vec<char>> = [
+            "",
+            "a",
+            "aap",
+            "apple",
+            "applet",
+            "apply",
+            "pen",
+            "pen",
+            "pineapple",
+            "pineapples",
+            "pine",
+        ]
+        .iter()
+        .map(|target| target.chars().collect())
+        .collect();
+        targets.sort();
+
+        let measure = LevenshteinDamerau::new(1, 1, 1, 1);
+
+        for &(source, _) in PAIRS {
+            let source: Vec<char> = source.chars().collect();
+            let mut aligner = PrefixAligner::new(measure.clone(), &source);
+
+            for target in &targets {
+                let alignment = measure.align(&source, target);
+                assert_eq!(aligner.distance(target), alignment.distance());
+                assert_eq!(
+                    aligner.align(target),
+                    AlignmentResult {
+                        distance: alignment.distance(),
+                        edit_script: alignment.edit_script(),
+                    }
+                );
+            }
+
+            // Targets do not have to be sorted, it is just more efficient.
+            for target in targets.iter().rev() {
+                assert_eq!(
+                    aligner.distance(target),
+                    measure.align(&source, target).distance()
+                );
+            }
+        }
+    }
+}
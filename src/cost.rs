@@ -0,0 +1,387 @@
+//! Edit operation costs.
+//!
+//! This module provides the `Cost` trait, which is implemented by types
+//! that can be used as edit operation costs, and the `ElementCosts` trait
+//! for costs that depend on the sequence elements.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Trait for edit operation costs.
+///
+/// Costs must be totally ordered, since alignment searches for the
+/// edit operations with the lowest cost.
+pub trait Cost: Copy + Debug + Eq + Hash + Ord {
+    /// The cost of doing nothing.
+    fn zero() -> Self;
+
+    /// The largest representable cost.
+    fn max_value() -> Self;
+
+    /// Add two costs, saturating at `max_value` rather than overflowing.
+    fn saturating_add(self, other: Self) -> Self;
+
+    /// Subtract two costs, saturating at zero rather than overflowing.
+    fn saturating_sub(self, other: Self) -> Self;
+
+    /// Convert the cost to a floating point number.
+    ///
+    /// This conversion is used to normalize distances and may lose
+    /// precision for large costs.
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! unsigned_cost {
+    ( $( $t:ty ),* ) => {
+        $(
+            impl Cost for $t {
+                fn zero() -> Self {
+                    0
+                }
+
+                fn max_value() -> Self {
+                    <$t>::max_value()
+                }
+
+                fn saturating_add(self, other: Self) -> Self {
+                    <$t>::saturating_add(self, other)
+                }
+
+                fn saturating_sub(self, other: Self) -> Self {
+                    <$t>::saturating_sub(self, other)
+                }
+
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+
+            impl Cost for Saturating<$t> {
+                fn zero() -> Self {
+                    Saturating(0)
+                }
+
+                fn max_value() -> Self {
+                    Saturating(<$t>::max_value())
+                }
+
+                fn saturating_add(self, other: Self) -> Self {
+                    Saturating(self.0.saturating_add(other.0))
+                }
+
+                fn saturating_sub(self, other: Self) -> Self {
+                    Saturating(self.0.saturating_sub(other.0))
+                }
+
+                fn to_f64(self) -> f64 {
+                    self.0 as f64
+                }
+            }
+        )*
+    };
+}
+
+/// Unsigned integer cost with saturating arithmetic.
+///
+/// Costs of measures are always added and subtracted with saturating
+/// arithmetic. This wrapper makes the saturating behavior explicit in the
+/// cost type, for use in code that is generic over costs.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Saturating<T>(pub T);
+
+unsigned_cost!(u8, u16, u32, u64, u128, usize);
+
+/// Floating point cost with a total order.
+///
+/// Floating point numbers are not totally ordered and cannot be hashed.
+/// This wrapper orders floating point numbers using IEEE 754 `totalOrder`,
+/// so that they can be used as costs. In this order, negative zero is
+/// smaller than positive zero and NaN is larger than infinity.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrderedFloat<F>(pub F);
+
+macro_rules! float_cost {
+    ( $( $t:ident => $bits:ty ),* ) => {
+        $(
+            impl Cost for OrderedFloat<$t> {
+                fn zero() -> Self {
+                    OrderedFloat(0.)
+                }
+
+                fn max_value() -> Self {
+                    OrderedFloat(std::$t::INFINITY)
+                }
+
+                fn saturating_add(self, other: Self) -> Self {
+                    OrderedFloat(self.0 + other.0)
+                }
+
+                fn saturating_sub(self, other: Self) -> Self {
+                    OrderedFloat((self.0 - other.0).max(0.))
+                }
+
+                fn to_f64(self) -> f64 {
+                    f64::from(self.0)
+                }
+            }
+
+            impl Eq for OrderedFloat<$t> {}
+
+            impl From<$t> for OrderedFloat<$t> {
+                fn from(value: $t) -> Self {
+                    OrderedFloat(value)
+                }
+            }
+
+            impl Hash for OrderedFloat<$t> {
+                fn hash<H>(&self, state: &mut H)
+                where
+                    H: Hasher,
+                {
+                    self.0.to_bits().hash(state)
+                }
+            }
+
+            impl Ord for OrderedFloat<$t> {
+                fn cmp(&self, other: &Self) -> Ordering {
+                    // Flip all bits except the sign bit of negative numbers,
+                    // so that the bits are ordered as signed integers.
+                    let mut left = self.0.to_bits() as $bits;
+                    let mut right = other.0.to_bits() as $bits;
+                    if left < 0 {
+                        left ^= <$bits>::max_value();
+                    }
+                    if right < 0 {
+                        right ^= <$bits>::max_value();
+                    }
+
+                    left.cmp(&right)
+                }
+            }
+
+            impl PartialEq for OrderedFloat<$t> {
+                fn eq(&self, other: &Self) -> bool {
+                    self.cmp(other) == Ordering::Equal
+                }
+            }
+
+            impl PartialOrd for OrderedFloat<$t> {
+                fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+        )*
+    };
+}
+
+float_cost!(f32 => i32, f64 => i64);
+
+/// Trait for element-dependent edit operation costs.
+///
+/// This trait provides the costs of deleting, inserting, and substituting
+/// sequence elements. It is used by measures such as
+/// `WeightedLevenshtein` to give operations costs that depend on the
+/// elements that they are applied to. `CostFns` implements this trait
+/// for closures. The type parameters `S` and `U` are the element types
+/// of the source and target sequences.
+pub trait ElementCosts<S, U = S> {
+    /// The type of the costs.
+    type Cost: Cost;
+
+    /// Get the cost of deleting an element of the source sequence.
+    fn delete_cost(&self, source: &S) -> Self::Cost;
+
+    /// Get the cost of inserting an element of the target sequence.
+    fn insert_cost(&self, target: &U) -> Self::Cost;
+
+    /// Get the cost of substituting an element of the source sequence by
+    /// an element of the target sequence.
+    fn substitute_cost(&self, source: &S, target: &U) -> Self::Cost;
+}
+
+impl<S, U, E> ElementCosts<S, U> for &E
+where
+    E: ElementCosts<S, U>,
+{
+    type Cost = E::Cost;
+
+    fn delete_cost(&self, source: &S) -> Self::Cost {
+        (*self).delete_cost(source)
+    }
+
+    fn insert_cost(&self, target: &U) -> Self::Cost {
+        (*self).insert_cost(target)
+    }
+
+    fn substitute_cost(&self, source: &S, target: &U) -> Self::Cost {
+        (*self).substitute_cost(source, target)
+    }
+}
+
+type CostFn<T, C> = Arc<dyn Fn(&T) -> C + Send + Sync>;
+
+type PairCostFn<S, U, C> = Arc<dyn Fn(&S, &U) -> C + Send + Sync>;
+
+/// Element-dependent costs given by closures.
+///
+/// Edit operations must be comparable and hashable, which closures are
+/// not. `CostFns` compares and hashes the closures by identity, so two
+/// `CostFns` are only equal when one is a clone of the other.
+pub struct CostFns<S, C, U = S> {
+    delete: CostFn<S, C>,
+    insert: CostFn<U, C>,
+    substitute: PairCostFn<S, U, C>,
+}
+
+impl<S, C, U> CostFns<S, C, U> {
+    /// Construct element costs from deletion, insertion, and substitution
+    /// cost functions.
+    pub fn new<D, I, F>(delete: D, insert: I, substitute: F) -> Self
+    where
+        D: Fn(&S) -> C + Send + Sync + 'static,
+        I: Fn(&U) -> C + Send + Sync + 'static,
+        F: Fn(&S, &U) -> C + Send + Sync + 'static,
+    {
+        CostFns {
+            delete: Arc::new(delete),
+            insert: Arc::new(insert),
+            substitute: Arc::new(substitute),
+        }
+    }
+}
+
+impl<S, C, U> Clone for CostFns<S, C, U> {
+    fn clone(&self) -> Self {
+        CostFns {
+            delete: self.delete.clone(),
+            insert: self.insert.clone(),
+            substitute: self.substitute.clone(),
+        }
+    }
+}
+
+impl<S, C, U> Debug for CostFns<S, C, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CostFns")
+            .field("delete", &(&*self.delete as *const _ as *const ()))
+            .field("insert", &(&*self.insert as *const _ as *const ()))
+            .field("substitute", &(&*self.substitute as *const _ as *const ()))
+            .finish()
+    }
+}
+
+impl<S, C, U> ElementCosts<S, U> for CostFns<S, C, U>
+where
+    C: Cost,
+{
+    type Cost = C;
+
+    fn delete_cost(&self, source: &S) -> C {
+        (self.delete)(source)
+    }
+
+    fn insert_cost(&self, target: &U) -> C {
+        (self.insert)(target)
+    }
+
+    fn substitute_cost(&self, source: &S, target: &U) -> C {
+        (self.substitute)(source, target)
+    }
+}
+
+impl<S, C, U> Eq for CostFns<S, C, U> {}
+
+impl<S, C, U> Hash for CostFns<S, C, U> {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        (&*self.delete as *const _ as *const ()).hash(state);
+        (&*self.insert as *const _ as *const ()).hash(state);
+        (&*self.substitute as *const _ as *const ()).hash(state);
+    }
+}
+
+impl<S, C, U> PartialEq for CostFns<S, C, U> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.delete, &other.delete)
+            && Arc::ptr_eq(&self.insert, &other.insert)
+            && Arc::ptr_eq(&self.substitute, &other.substitute)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cost, CostFns, ElementCosts, OrderedFloat, Saturating};
+
+    #[test]
+    fn saturating_add_test() {
+        assert_eq!(Cost::saturating_add(3u8, 4), 7);
+        assert_eq!(Cost::saturating_add(200u8, 100), u8::max_value());
+        assert_eq!(
+            Saturating(200u8).saturating_add(Saturating(100)),
+            Saturating(u8::max_value())
+        );
+        assert_eq!(
+            OrderedFloat(0.5f32).saturating_add(OrderedFloat(0.25)),
+            OrderedFloat(0.75)
+        );
+        assert_eq!(
+            OrderedFloat::<f64>::max_value().saturating_add(OrderedFloat(1.)),
+            OrderedFloat::max_value()
+        );
+    }
+
+    #[test]
+    fn to_f64_test() {
+        assert_eq!(3u8.to_f64(), 3.);
+        assert_eq!(Saturating(7usize).to_f64(), 7.);
+        assert_eq!(OrderedFloat(0.5f32).to_f64(), 0.5);
+        assert_eq!(OrderedFloat(0.25f64).to_f64(), 0.25);
+    }
+
+    #[test]
+    fn saturating_sub_test() {
+        assert_eq!(Cost::saturating_sub(4u8, 3), 1);
+        assert_eq!(Cost::saturating_sub(3u8, 4), 0);
+        assert_eq!(Saturating(3u8).saturating_sub(Saturating(4)), Saturating(0));
+        assert_eq!(
+            OrderedFloat(0.5f32).saturating_sub(OrderedFloat(0.75)),
+            OrderedFloat(0.)
+        );
+    }
+
+    #[test]
+    fn ordered_float_test() {
+        assert!(OrderedFloat(0.5f64) < OrderedFloat(1.));
+        assert!(OrderedFloat(-0.0f64) < OrderedFloat(0.0));
+        assert!(OrderedFloat(std::f32::INFINITY) < OrderedFloat(std::f32::NAN));
+        assert_eq!(OrderedFloat(std::f32::NAN), OrderedFloat(std::f32::NAN));
+        assert!(OrderedFloat(-1.0f64) < OrderedFloat(-0.5));
+        assert!(OrderedFloat(std::f64::NEG_INFINITY) < OrderedFloat(-1.));
+        assert!(OrderedFloat(-std::f64::NAN) < OrderedFloat(std::f64::NEG_INFINITY));
+    }
+
+    #[test]
+    fn cost_fns_test() {
+        let costs = CostFns::new(
+            |_: &char| 2u8,
+            |_: &char| 3,
+            |&source: &char, &target: &char| if source == target { 0 } else { 1 },
+        );
+
+        assert_eq!(costs.delete_cost(&'a'), 2);
+        assert_eq!(costs.insert_cost(&'a'), 3);
+        assert_eq!(costs.substitute_cost(&'a', &'a'), 0);
+        assert_eq!(costs.substitute_cost(&'a', &'b'), 1);
+
+        assert_eq!(costs, costs.clone());
+        assert_ne!(
+            costs,
+            CostFns::new(|_: &char| 2u8, |_: &char| 3, |_: &char, _: &char| 1)
+        );
+    }
+}
@@ -0,0 +1,551 @@
+use crate::cost::Cost;
+use crate::error::AlignError;
+use crate::op::{EditScript, IndexedOperation};
+use crate::{CostMatrix, SeqPair};
+
+/// Dynamic time warping (DTW).
+///
+/// Dynamic time warping aligns sequences by warping them in time: every
+/// element of the source sequence is aligned with one or more elements of
+/// the target sequence and vice versa. The cost of an alignment is the
+/// sum of the local distances of the aligned elements, where the local
+/// distance is given by a user-supplied function. Since elements are
+/// only compared through the local distance, they do not have to be
+/// `Eq`, which makes this measure suitable for numeric feature
+/// sequences.
+///
+/// The warping path consists of the following moves:
+///
+/// * Diagonal: align the next source element with the next target
+///   element;
+/// * Vertical: align the next source element with the current target
+///   element;
+/// * Horizontal: align the current source element with the next target
+///   element.
+///
+/// The weights of the moves are determined by the step pattern (see
+/// `StepPattern`) and the warping path can be restricted to a window
+/// (see `Window`). Dynamic time warping does not fit the edit operations
+/// of `Measure`, alignments are computed with `Dtw::align`.
+///
+/// ```
+/// use seqalign::cost::OrderedFloat;
+/// use seqalign::measures::{Dtw, Window};
+///
+/// let dtw = Dtw::new(|a: &f64, b: &f64| OrderedFloat((a - b).abs()))
+///     .with_window(Window::SakoeChiba(1));
+///
+/// let source = &[0., 1., 2., 1., 0.];
+/// let target = &[0., 1., 1., 2., 1., 0.];
+/// assert_eq!(dtw.distance(source, target), OrderedFloat(0.));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Dtw<F> {
+    local_distance: F,
+    step_pattern: StepPattern,
+    window: Window,
+}
+
+impl<F> Dtw<F> {
+    /// Construct a DTW measure with the given local distance function.
+    ///
+    /// The measure uses the `Symmetric1` step pattern and no window.
+    pub fn new(local_distance: F) -> Self {
+        Dtw {
+            local_distance,
+            step_pattern: StepPattern::Symmetric1,
+            window: Window::Unconstrained,
+        }
+    }
+
+    /// Use the given step pattern.
+    pub fn with_step_pattern(mut self, step_pattern: StepPattern) -> Self {
+        self.step_pattern = step_pattern;
+        self
+    }
+
+    /// Restrict warping paths to the given window.
+    pub fn with_window(mut self, window: Window) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Align two sequences.
+    ///
+    /// Panics when the window does not contain a warping path, e.g.
+    /// because only one of the sequences is empty. Use `try_align` to get
+    /// an error instead.
+    pub fn align<'a, T, C>(&'a self, source: &'a [T], target: &'a [T]) -> DtwAlignment<'a, F, T, C>
+    where
+        C: Cost,
+        F: Fn(&T, &T) -> C,
+    {
+        self.try_align(source, target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Align two sequences.
+    ///
+    /// An error is returned when the window does not contain a warping
+    /// path, e.g. because only one of the sequences is empty.
+    pub fn try_align<'a, T, C>(
+        &'a self,
+        source: &'a [T],
+        target: &'a [T],
+    ) -> Result<DtwAlignment<'a, F, T, C>, AlignError>
+    where
+        C: Cost,
+        F: Fn(&T, &T) -> C,
+    {
+        let pair = SeqPair { source, target };
+        let mut cost_matrix = CostMatrix::new(source.len() + 1, target.len() + 1);
+        self.fill(&pair, &mut cost_matrix)?;
+
+        Ok(DtwAlignment {
+            measure: self,
+            pair,
+            cost_matrix,
+        })
+    }
+
+    /// Compute the DTW distance of two sequences.
+    ///
+    /// This method gives the same result as `align(source,
+    /// target).distance()`, but only keeps two rows of the cost matrix in
+    /// memory. Panics when the window does not contain a warping path.
+    pub fn distance<T, C>(&self, source: &[T], target: &[T]) -> C
+    where
+        C: Cost,
+        F: Fn(&T, &T) -> C,
+    {
+        let pair = SeqPair { source, target };
+        let mut cost_matrix = CostMatrix::with_stored_rows(source.len() + 1, target.len() + 1, 2);
+        self.fill(&pair, &mut cost_matrix)
+            .unwrap_or_else(|err| panic!("{}", err));
+
+        cost_matrix[(source.len(), target.len())]
+    }
+
+    /// Fill the cost matrix.
+    ///
+    /// Cells that are outside the window or that cannot be reached get
+    /// the maximum cost.
+    fn fill<T, C>(
+        &self,
+        pair: &SeqPair<T>,
+        cost_matrix: &mut CostMatrix<C>,
+    ) -> Result<(), AlignError>
+    where
+        C: Cost,
+        F: Fn(&T, &T) -> C,
+    {
+        let source_len = pair.source.len();
+        let target_len = pair.target.len();
+
+        for source_idx in 0..=source_len {
+            for target_idx in 0..=target_len {
+                cost_matrix[(source_idx, target_idx)] = if source_idx == 0 && target_idx == 0 {
+                    C::zero()
+                } else if source_idx == 0
+                    || target_idx == 0
+                    || !self
+                        .window
+                        .contains(source_len, target_len, source_idx, target_idx)
+                {
+                    C::max_value()
+                } else {
+                    let local_distance = (self.local_distance)(
+                        &pair.source[source_idx - 1],
+                        &pair.target[target_idx - 1],
+                    );
+                    self.step_costs(cost_matrix, local_distance, source_idx, target_idx)
+                        .iter()
+                        .map(|&(_, cost)| cost)
+                        .fold(C::max_value(), Ord::min)
+                };
+            }
+        }
+
+        if source_len + target_len != 0 && cost_matrix[(source_len, target_len)] == C::max_value() {
+            return Err(AlignError::EmptyWindow {
+                source_len,
+                target_len,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get the costs of reaching a cell with each move.
+    fn step_costs<C>(
+        &self,
+        cost_matrix: &CostMatrix<C>,
+        local_distance: C,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> [(DtwOp, C); 3]
+    where
+        C: Cost,
+    {
+        let diagonal_distance = match self.step_pattern {
+            StepPattern::Symmetric1 => local_distance,
+            StepPattern::Symmetric2 => local_distance.saturating_add(local_distance),
+        };
+
+        [
+            (
+                DtwOp::Diagonal,
+                cost_matrix[(source_idx - 1, target_idx - 1)].saturating_add(diagonal_distance),
+            ),
+            (
+                DtwOp::Vertical,
+                cost_matrix[(source_idx - 1, target_idx)].saturating_add(local_distance),
+            ),
+            (
+                DtwOp::Horizontal,
+                cost_matrix[(source_idx, target_idx - 1)].saturating_add(local_distance),
+            ),
+        ]
+    }
+}
+
+/// DTW step pattern.
+///
+/// The step pattern determines the weights of the local distances in
+/// the moves of the warping path.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum StepPattern {
+    /// All moves have weight *1*. Diagonal moves are favored, since they
+    /// advance in both sequences for the cost of one local distance.
+    Symmetric1,
+
+    /// Diagonal moves have weight *2*, horizontal and vertical moves
+    /// weight *1*. The cost of every warping path is then a weighted sum
+    /// over *m + n* local distances for sequences of lengths *m* and *n*,
+    /// so that the distance can be normalized by *m + n*.
+    Symmetric2,
+}
+
+/// DTW window constraint.
+///
+/// A window restricts the cells of the cost matrix that a warping path
+/// can pass through. This avoids pathological warpings where a single
+/// element is aligned with a large part of the other sequence and
+/// speeds up alignment of long sequences.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    /// Warping paths are not restricted.
+    Unconstrained,
+
+    /// Sakoe-Chiba band with the given radius.
+    ///
+    /// Elements can only be aligned when they are at most the radius
+    /// away from the diagonal. The diagonal is scaled to the lengths of
+    /// the sequences, so that the band always contains a warping path.
+    SakoeChiba(usize),
+
+    /// Itakura parallelogram with the given maximum slope.
+    ///
+    /// Warping paths must stay in the parallelogram with slopes
+    /// *1 / max_slope* and *max_slope* through the first and last
+    /// element pairs. If the ratio of the sequence lengths is outside
+    /// these slopes, there is no warping path. The maximum slope should
+    /// be larger than *1*, a common choice is *2*.
+    Itakura(f64),
+}
+
+impl Window {
+    /// Check whether the window contains the pair of the elements with
+    /// (one-based) indices `source_idx` and `target_idx`.
+    fn contains(
+        self,
+        source_len: usize,
+        target_len: usize,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> bool {
+        // Zero-based offsets from the first and last element pair.
+        let (i, j) = (source_idx - 1, target_idx - 1);
+        let (n, m) = (source_len - 1, target_len - 1);
+
+        match self {
+            Window::Unconstrained => true,
+            Window::SakoeChiba(radius) => {
+                diagonal_offset(i, n, j, m) <= radius || diagonal_offset(j, m, i, n) <= radius
+            }
+            Window::Itakura(max_slope) => {
+                let (i, j, n, m) = (i as f64, j as f64, n as f64, m as f64);
+                j <= max_slope * i
+                    && i <= max_slope * j
+                    && (m - j) <= max_slope * (n - i)
+                    && (n - i) <= max_slope * (m - j)
+            }
+        }
+    }
+}
+
+/// Get the distance of `j` from the scaled diagonal at `i`, where `i` is
+/// in *[0, n]* and `j` in *[0, m]*.
+fn diagonal_offset(i: usize, n: usize, j: usize, m: usize) -> usize {
+    // Rounded i · m / n.
+    let diagonal = if n == 0 { 0 } else { (2 * i * m + n) / (2 * n) };
+    if j > diagonal {
+        j - diagonal
+    } else {
+        diagonal - j
+    }
+}
+
+/// DTW move.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DtwOp {
+    /// Advance in both sequences.
+    Diagonal,
+
+    /// Advance in the source sequence, repeating the target element.
+    Vertical,
+
+    /// Advance in the target sequence, repeating the source element.
+    Horizontal,
+}
+
+/// Dynamic time warping alignment.
+pub struct DtwAlignment<'a, F, T, C> {
+    measure: &'a Dtw<F>,
+    pair: SeqPair<'a, T>,
+    cost_matrix: CostMatrix<C>,
+}
+
+impl<'a, F, T, C> DtwAlignment<'a, F, T, C>
+where
+    C: Cost,
+    F: Fn(&T, &T) -> C,
+{
+    /// Get the DTW distance.
+    pub fn distance(&self) -> C {
+        self.cost_matrix[(self.pair.source.len(), self.pair.target.len())]
+    }
+
+    /// Return the moves of the warping path.
+    ///
+    /// Every move aligns a source element with a target element. In
+    /// contrast to edit scripts of other measures, the indices of a move
+    /// are those of the elements that it aligns. If there are multiple
+    /// optimal warping paths, diagonal moves are preferred over vertical
+    /// moves over horizontal moves.
+    ///
+    /// Panics when the warping path cannot be traced back. Use
+    /// `try_edit_script` to get an error instead.
+    pub fn edit_script(&self) -> EditScript<DtwOp> {
+        self.try_edit_script()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Return the moves of the warping path.
+    ///
+    /// This method is the same as `edit_script`, but returns an error
+    /// when none of the moves leads to the cost of a cell on the warping
+    /// path.
+    pub fn try_edit_script(&self) -> Result<EditScript<DtwOp>, AlignError> {
+        let mut script = Vec::new();
+
+        let (mut source_idx, mut target_idx) = (self.pair.source.len(), self.pair.target.len());
+        while source_idx > 0 && target_idx > 0 {
+            let local_distance = (self.measure.local_distance)(
+                &self.pair.source[source_idx - 1],
+                &self.pair.target[target_idx - 1],
+            );
+            let (op, _) = self
+                .measure
+                .step_costs(&self.cost_matrix, local_distance, source_idx, target_idx)
+                .iter()
+                .cloned()
+                .find(|&(_, cost)| cost == self.cost_matrix[(source_idx, target_idx)])
+                .ok_or(AlignError::CannotBacktrack {
+                    source_idx,
+                    target_idx,
+                })?;
+
+            script.push(IndexedOperation::new(op, source_idx - 1, target_idx - 1));
+
+            match op {
+                DtwOp::Diagonal => {
+                    source_idx -= 1;
+                    target_idx -= 1;
+                }
+                DtwOp::Vertical => source_idx -= 1,
+                DtwOp::Horizontal => target_idx -= 1,
+            }
+        }
+
+        script.reverse();
+        Ok(script)
+    }
+
+    /// Get the warping path.
+    ///
+    /// The warping path consists of the (zero-based) indices of the
+    /// aligned source and target elements.
+    pub fn warping_path(&self) -> Vec<(usize, usize)> {
+        self.edit_script()
+            .iter()
+            .map(|op| (op.source_idx(), op.target_idx()))
+            .collect()
+    }
+
+    /// Get the cost matrix.
+    ///
+    /// Cells that are outside the window or that cannot be reached have
+    /// the maximum cost.
+    pub fn cost_matrix(&self) -> &CostMatrix<C> {
+        &self.cost_matrix
+    }
+
+    /// Get the sequence pair associated with this alignment.
+    pub fn seq_pair(&self) -> &SeqPair<'_, T> {
+        &self.pair
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cost::OrderedFloat;
+    use crate::error::AlignError;
+    use crate::op::IndexedOperation;
+
+    use super::{Dtw, DtwOp::*, StepPattern, Window};
+
+    fn abs_diff(a: &i32, b: &i32) -> usize {
+        (a - b).abs() as usize
+    }
+
+    #[test]
+    fn dtw_test() {
+        let dtw = Dtw::new(abs_diff);
+
+        let source = [1, 2, 3, 3, 5];
+        let target = [1, 2, 2, 3, 5, 5];
+        let alignment = dtw.align(&source, &target);
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(dtw.distance(&source, &target), 0);
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(Diagonal, 0, 0),
+                IndexedOperation::new(Diagonal, 1, 1),
+                IndexedOperation::new(Horizontal, 1, 2),
+                IndexedOperation::new(Diagonal, 2, 3),
+                IndexedOperation::new(Vertical, 3, 3),
+                IndexedOperation::new(Diagonal, 4, 4),
+                IndexedOperation::new(Horizontal, 4, 5),
+            ]
+        );
+        assert_eq!(
+            alignment.warping_path(),
+            vec![(0, 0), (1, 1), (1, 2), (2, 3), (3, 3), (4, 4), (4, 5)]
+        );
+
+        let source = [0, 4, 0];
+        let target = [0, 2, 2, 0];
+        let alignment = dtw.align(&source, &target);
+        assert_eq!(alignment.distance(), 4);
+        assert_eq!(
+            alignment.warping_path(),
+            vec![(0, 0), (0, 1), (1, 2), (2, 3)]
+        );
+
+        let empty: &[i32] = &[];
+        assert_eq!(dtw.distance(empty, empty), 0);
+        assert!(dtw.align(empty, empty).edit_script().is_empty());
+        assert_eq!(
+            dtw.try_align(&source, empty).err(),
+            Some(AlignError::EmptyWindow {
+                source_len: 3,
+                target_len: 0
+            })
+        );
+    }
+
+    #[test]
+    fn dtw_float_test() {
+        let dtw = Dtw::new(|a: &f64, b: &f64| OrderedFloat((a - b).abs()));
+
+        let source = [0.0, 0.5, 1.0];
+        let target = [0.0, 1.0];
+        let alignment = dtw.align(&source, &target);
+        assert_eq!(alignment.distance(), OrderedFloat(0.5));
+        assert_eq!(alignment.warping_path(), vec![(0, 0), (1, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn step_pattern_test() {
+        let source = [0, 1, 2];
+        let target = [0, 2];
+
+        let symmetric1 = Dtw::new(abs_diff);
+        assert_eq!(symmetric1.distance(&source, &target), 1);
+
+        // Every path has weight m + n, diagonal moves count twice.
+        let symmetric2 = Dtw::new(abs_diff).with_step_pattern(StepPattern::Symmetric2);
+        let alignment = symmetric2.align(&source, &target);
+        assert_eq!(alignment.distance(), 1);
+        assert_eq!(alignment.warping_path(), vec![(0, 0), (1, 0), (2, 1)]);
+
+        let source = [0, 3];
+        let target = [0, 3];
+        assert_eq!(symmetric2.distance(&source, &target), 0);
+        let source = [0, 3];
+        let target = [1, 3];
+        assert_eq!(symmetric2.distance(&source, &target), 2);
+    }
+
+    #[test]
+    fn sakoe_chiba_test() {
+        // Aligning the nines requires warping far from the diagonal.
+        let source = [0, 9, 0, 0, 0, 0, 0];
+        let target = [0, 0, 0, 0, 0, 9, 0];
+
+        let unconstrained = Dtw::new(abs_diff);
+        assert_eq!(unconstrained.distance(&source, &target), 0);
+
+        let sakoe_chiba = Dtw::new(abs_diff).with_window(Window::SakoeChiba(1));
+        let alignment = sakoe_chiba.align(&source, &target);
+        assert_eq!(alignment.distance(), 18);
+        assert!(alignment
+            .warping_path()
+            .iter()
+            .all(|&(i, j)| (i as isize - j as isize).abs() <= 1));
+
+        // The band is scaled to the sequence lengths.
+        let long = [0; 10];
+        let short = [0; 3];
+        let sakoe_chiba = Dtw::new(abs_diff).with_window(Window::SakoeChiba(0));
+        assert_eq!(sakoe_chiba.distance(&long, &short), 0);
+        assert_eq!(sakoe_chiba.distance(&short, &long), 0);
+        assert_eq!(sakoe_chiba.distance(&long[..1], &short), 0);
+    }
+
+    #[test]
+    fn itakura_test() {
+        let itakura = Dtw::new(abs_diff).with_window(Window::Itakura(2.));
+
+        let source = [0, 9, 0, 0, 0, 0, 0];
+        let target = [0, 0, 0, 0, 0, 9, 0];
+        let alignment = itakura.align(&source, &target);
+        assert_eq!(alignment.distance(), 18);
+        assert!(alignment.warping_path().iter().all(|&(i, j)| {
+            let (i, j) = (i as f64, j as f64);
+            j <= 2. * i && i <= 2. * j && (6. - j) <= 2. * (6. - i) && (6. - i) <= 2. * (6. - j)
+        }));
+
+        // The ratio of the sequence lengths exceeds the maximum slope.
+        assert_eq!(
+            itakura.try_align(&[0; 7], &[0; 4]).err(),
+            Some(AlignError::EmptyWindow {
+                source_len: 7,
+                target_len: 4
+            })
+        );
+        assert!(itakura.try_align(&[0; 7], &[0; 5]).is_ok());
+    }
+}
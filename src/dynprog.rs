@@ -1,199 +1,1026 @@
-use std::collections::{HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::iter;
 
-use crate::op::{Backtrack, BestCost, IndexedOperation, Operation};
-use crate::{Measure, SeqPair};
+use crate::cost::Cost;
+use crate::error::AlignError;
+use crate::op::{Backtrack, BestCost, EditScript, IndexedOperation, Operation};
+use crate::{AlignmentMode, CostMatrix, Measure, SeqPair};
 
 /// Trait enabling alignment of all `Measure`s.
 ///
 /// This trait is used to implement alignment using dynamic programming
 /// for every type that implements the `Measure` trait.
-pub trait Align<'a, M, T>
+pub trait Align<'a, M, S, U = S>
 where
-    M: Measure<T>,
-    T: Eq,
+    M: Measure<S, U>,
 {
     /// Align two sequences.
     ///
     /// This function aligns two sequences and returns the alignment.
-    fn align(&'a self, source: &'a [T], target: &'a [T]) -> Alignment<'a, M, T>;
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix. Use `try_align` to get an error instead.
+    fn align(&'a self, source: &'a [S], target: &'a [U]) -> Alignment<'a, M, S, U>;
+
+    /// Align two sequences.
+    ///
+    /// This function aligns two sequences and returns the alignment. An
+    /// error is returned when none of the measure's operations can be
+    /// applied to a cell of the cost matrix.
+    fn try_align(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+    ) -> Result<Alignment<'a, M, S, U>, AlignError>;
+
+    /// Align two sequences using the given alignment mode.
+    ///
+    /// This function is the same as `align`, but can leave leading and
+    /// trailing elements of the sequences unaligned without any cost
+    /// (semi-global alignment). See `AlignmentMode` for more
+    /// information.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix. Use `try_align_with_mode` to get an error
+    /// instead.
+    fn align_with_mode(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        mode: AlignmentMode,
+    ) -> Alignment<'a, M, S, U>;
+
+    /// Align two sequences using the given alignment mode.
+    ///
+    /// This function is the same as `align_with_mode`, but returns an
+    /// error when none of the measure's operations can be applied to a
+    /// cell of the cost matrix.
+    fn try_align_with_mode(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        mode: AlignmentMode,
+    ) -> Result<Alignment<'a, M, S, U>, AlignError>;
+
+    /// Align two sequences if their distance is at most `max_distance`.
+    ///
+    /// This function only fills the band of the cost matrix that can
+    /// still lead to a distance of at most `max_distance` (Ukkonen's
+    /// cut-off). `None` is returned as soon as it is certain that the
+    /// distance exceeds `max_distance`, for instance when every cell in
+    /// a row exceeds `max_distance`. Otherwise, the alignment is returned.
+    ///
+    /// Cells outside the band are not computed. In the cost matrix of
+    /// the returned alignment, these cells have the cost
+    /// `Cost::max_value()`.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix. Use `try_align_within` to get an error
+    /// instead.
+    fn align_within(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        max_distance: M::Cost,
+    ) -> Option<Alignment<'a, M, S, U>>;
+
+    /// Align two sequences if their distance is at most `max_distance`.
+    ///
+    /// This function is the same as `align_within`, but returns an
+    /// error when none of the measure's operations can be applied to a
+    /// cell of the cost matrix.
+    fn try_align_within(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        max_distance: M::Cost,
+    ) -> Result<Option<Alignment<'a, M, S, U>>, AlignError>;
+
+    /// Compute the edit distance of two sequences.
+    ///
+    /// This function gives the same result as `align(source,
+    /// target).distance()`, but does not construct the full cost
+    /// matrix. Only the rows of the cost matrix that the measure's
+    /// operations can backtrack to are kept in memory. E.g. two rows
+    /// are used for Levenshtein distance and three rows for
+    /// Levenshtein-Damerau distance.
+    ///
+    /// If the measure provides a faster algorithm through
+    /// `Measure::fast_distance`, that algorithm is used instead.
+    fn distance(&self, source: &[S], target: &[U]) -> M::Cost;
 }
 
-impl<'a, M, T> Align<'a, M, T> for M
+impl<'a, M, S, U> Align<'a, M, S, U> for M
 where
-    M: Measure<T>,
-    T: Eq,
+    M: Measure<S, U>,
 {
-    fn align(&'a self, source: &'a [T], target: &'a [T]) -> Alignment<'a, M, T> {
+    fn align(&'a self, source: &'a [S], target: &'a [U]) -> Alignment<'a, M, S, U> {
+        self.try_align(source, target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_align(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+    ) -> Result<Alignment<'a, M, S, U>, AlignError> {
+        self.try_align_with_mode(source, target, AlignmentMode::global())
+    }
+
+    fn align_with_mode(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        mode: AlignmentMode,
+    ) -> Alignment<'a, M, S, U> {
+        self.try_align_with_mode(source, target, mode)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_align_with_mode(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        mode: AlignmentMode,
+    ) -> Result<Alignment<'a, M, S, U>, AlignError> {
+        let pair = SeqPair { source, target };
+
+        let mut cost_matrix = CostMatrix::new(pair.source.len() + 1, pair.target.len() + 1);
+        fill_cost_matrix(self, &pair, &mut cost_matrix, mode)?;
+
+        Ok(Alignment {
+            measure: self,
+            pair,
+            cost_matrix,
+            mode,
+            max_distance: None,
+        })
+    }
+
+    fn align_within(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        max_distance: M::Cost,
+    ) -> Option<Alignment<'a, M, S, U>> {
+        self.try_align_within(source, target, max_distance)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_align_within(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        max_distance: M::Cost,
+    ) -> Result<Option<Alignment<'a, M, S, U>>, AlignError> {
+        self.check_sequences(source, target)?;
+
         let pair = SeqPair { source, target };
 
         let source_len = pair.source.len() + 1;
         let target_len = pair.target.len() + 1;
 
-        let mut cost_matrix = vec![vec![0; target_len]; source_len];
+        let (row_lookback, column_lookback) = max_lookback(self, &pair);
+        let row_lookback = row_lookback.max(1);
 
-        // Fill first row. This is separated from the rest of the matrix fill
-        // because we do not want to fill cell [0][0].
-        for target_idx in 1..target_len {
-            cost_matrix[0][target_idx] = self
-                .best_cost(&pair, &cost_matrix, 0, target_idx)
-                .expect("No applicable operation");
-        }
+        // Cells outside the band are never computed, they get the maximum
+        // cost. Since operations saturate at the maximum cost, cells that
+        // are computed from these cells will also exceed the maximum
+        // distance.
+        let mut cost_matrix = CostMatrix::from_elem(M::Cost::max_value(), source_len, target_len);
+        cost_matrix[(0, 0)] = M::Cost::zero();
 
-        // Fill the matrix
-        for source_idx in 1..source_len {
-            for target_idx in 0..target_len {
-                cost_matrix[source_idx][target_idx] = self
-                    .best_cost(&pair, &cost_matrix, source_idx, target_idx)
-                    .expect("No applicable operation");
+        // The first and last column of each row that is within the maximum
+        // distance, or None if the whole row exceeds the maximum distance.
+        let mut bands: Vec<Option<(usize, usize)>> = Vec::with_capacity(source_len);
+
+        for source_idx in 0..source_len {
+            // A cell can only be within the maximum distance if one of the
+            // cells that it is computed from is within the maximum distance.
+            let (start, mut end) = if source_idx == 0 {
+                (0, 0)
+            } else {
+                let band = bands[source_idx.saturating_sub(row_lookback)..source_idx]
+                    .iter()
+                    .flatten()
+                    .fold(None, |band: Option<(usize, usize)>, &(start, end)| {
+                        Some(band.map_or((start, end), |(band_start, band_end)| {
+                            (band_start.min(start), band_end.max(end))
+                        }))
+                    });
+
+                match band {
+                    Some((start, end)) => (start, end + column_lookback),
+                    None => return Ok(None),
+                }
+            };
+
+            let mut band = None;
+            let mut target_idx = start;
+            while target_idx < target_len && target_idx <= end {
+                // Do not fill cell [0][0].
+                if source_idx != 0 || target_idx != 0 {
+                    cost_matrix[(source_idx, target_idx)] = self
+                        .best_cost(&pair, &cost_matrix, source_idx, target_idx)
+                        .ok_or(AlignError::NoApplicableOperation {
+                            source_idx,
+                            target_idx,
+                        })?;
+                }
+
+                if cost_matrix[(source_idx, target_idx)] <= max_distance {
+                    band = Some((band.map_or(target_idx, |(start, _)| start), target_idx));
+                    end = end.max(target_idx + column_lookback);
+                }
+
+                target_idx += 1;
             }
+
+            bands.push(band);
         }
 
-        Alignment {
+        if cost_matrix[(source_len - 1, target_len - 1)] > max_distance {
+            return Ok(None);
+        }
+
+        Ok(Some(Alignment {
             measure: self,
             pair,
             cost_matrix,
+            mode: AlignmentMode::global(),
+            max_distance: Some(max_distance),
+        }))
+    }
+
+    fn distance(&self, source: &[S], target: &[U]) -> M::Cost {
+        if let Some(distance) = self.fast_distance(source, target) {
+            return distance;
+        }
+
+        let pair = SeqPair { source, target };
+        last_row(self, &pair).unwrap_or_else(|err| panic!("{}", err))[pair.target.len()]
+    }
+}
+
+/// Compute the last row of the cost matrix.
+///
+/// Only the rows of the cost matrix that the measure's operations can
+/// backtrack to are kept in memory.
+pub(crate) fn last_row<M, S, U>(
+    measure: &M,
+    pair: &SeqPair<S, U>,
+) -> Result<Vec<M::Cost>, AlignError>
+where
+    M: Measure<S, U>,
+{
+    let source_len = pair.source.len() + 1;
+    let target_len = pair.target.len() + 1;
+
+    // The number of rows that we need to keep: the current row and
+    // the rows that the operations can backtrack to.
+    let (row_lookback, _) = max_lookback(measure, pair);
+    let n_rows = (row_lookback + 1).min(source_len);
+
+    let mut cost_matrix = CostMatrix::with_stored_rows(source_len, target_len, n_rows);
+    fill_cost_matrix(measure, pair, &mut cost_matrix, AlignmentMode::global())?;
+
+    Ok(cost_matrix.row(source_len - 1).to_vec())
+}
+
+/// Fill the cost matrix.
+///
+/// The rows of the cost matrix are filled in order, so this function can
+/// also be used with cost matrices that only store the last rows.
+pub(crate) fn fill_cost_matrix<M, S, U>(
+    measure: &M,
+    pair: &SeqPair<S, U>,
+    cost_matrix: &mut CostMatrix<M::Cost>,
+    mode: AlignmentMode,
+) -> Result<(), AlignError>
+where
+    M: Measure<S, U>,
+{
+    measure.check_sequences(pair.source, pair.target)?;
+
+    let (source_len, target_len) = cost_matrix.shape();
+
+    // Fill first row. This is separated from the rest of the matrix fill
+    // because we do not want to fill cell [0][0].
+    for target_idx in 1..target_len {
+        cost_matrix[(0, target_idx)] = if mode.free_target_leading {
+            M::Cost::zero()
+        } else {
+            fill_cell(measure, pair, cost_matrix, 0, target_idx)?
+        };
+    }
+
+    // Fill the matrix
+    for source_idx in 1..source_len {
+        cost_matrix[(source_idx, 0)] = if mode.free_source_leading {
+            M::Cost::zero()
+        } else {
+            fill_cell(measure, pair, cost_matrix, source_idx, 0)?
+        };
+
+        for target_idx in 1..target_len {
+            cost_matrix[(source_idx, target_idx)] =
+                fill_cell(measure, pair, cost_matrix, source_idx, target_idx)?;
         }
     }
+
+    Ok(())
+}
+
+#[inline]
+fn fill_cell<M, S, U>(
+    measure: &M,
+    pair: &SeqPair<S, U>,
+    cost_matrix: &CostMatrix<M::Cost>,
+    source_idx: usize,
+    target_idx: usize,
+) -> Result<M::Cost, AlignError>
+where
+    M: Measure<S, U>,
+{
+    measure
+        .best_cost(pair, cost_matrix, source_idx, target_idx)
+        .ok_or(AlignError::NoApplicableOperation {
+            source_idx,
+            target_idx,
+        })
+}
+
+/// Get the maximum number of rows and columns that the measure's
+/// operations look back.
+///
+/// The look-back is determined by backtracking from the last cell of the
+/// cost matrix with every operation. If an operation has an unbounded
+/// look-back, all rows and columns are looked back.
+pub(crate) fn max_lookback<M, S, U>(measure: &M, pair: &SeqPair<S, U>) -> (usize, usize)
+where
+    M: Measure<S, U>,
+{
+    let source_idx = pair.source.len();
+    let target_idx = pair.target.len();
+
+    if measure
+        .operations()
+        .iter()
+        .any(Operation::has_unbounded_lookback)
+    {
+        return (source_idx, target_idx);
+    }
+
+    measure
+        .operations()
+        .iter()
+        .filter_map(|op| op.backtrack(pair, source_idx, target_idx))
+        .fold(
+            (0, 0),
+            |(rows, columns), (from_source_idx, from_target_idx)| {
+                (
+                    rows.max(source_idx - from_source_idx),
+                    columns.max(target_idx - from_target_idx),
+                )
+            },
+        )
 }
 
 /// Edit distance cost matrix.
-pub struct Alignment<'a, M, T>
+pub struct Alignment<'a, M, S, U = S>
 where
-    M: Measure<T>,
-    T: Eq,
+    M: Measure<S, U>,
 {
-    measure: &'a M,
-    pair: SeqPair<'a, T>,
-    cost_matrix: Vec<Vec<usize>>,
+    pub(crate) measure: &'a M,
+    pub(crate) pair: SeqPair<'a, S, U>,
+    pub(crate) cost_matrix: CostMatrix<M::Cost>,
+    pub(crate) mode: AlignmentMode,
+
+    /// The maximum distance of alignments computed with
+    /// `Align::align_within`. Cells with a higher cost are not computed.
+    pub(crate) max_distance: Option<M::Cost>,
 }
 
-impl<'a, M, T> Alignment<'a, M, T>
+impl<'a, M, S, U> Alignment<'a, M, S, U>
 where
-    M: Measure<T>,
-    T: Eq,
+    M: Measure<S, U>,
 {
     /// Get the edit distance.
-    pub fn distance(&self) -> usize {
-        self.cost_matrix[self.cost_matrix.len() - 1][self.cost_matrix[0].len() - 1]
+    ///
+    /// In semi-global alignment, this is the cost of the end cell (see
+    /// `end_cell`).
+    pub fn distance(&self) -> M::Cost {
+        self.cost_matrix[self.end_cell()]
+    }
+
+    /// Get the cell of the cost matrix where the alignment ends.
+    ///
+    /// This is the lower-right cell, unless the alignment mode has free
+    /// trailing elements. In that case, it is the cell with the lowest
+    /// cost in the last column (free trailing source elements) and/or
+    /// the last row (free trailing target elements). Ties are broken in
+    /// favor of the lower-right cell, then the last column, then the last
+    /// row.
+    pub fn end_cell(&self) -> (usize, usize) {
+        let source_len = self.pair.source.len();
+        let target_len = self.pair.target.len();
+
+        let mut end = (source_len, target_len);
+        if self.mode.free_source_trailing {
+            for source_idx in 0..source_len {
+                if self.cost_matrix[(source_idx, target_len)] < self.cost_matrix[end] {
+                    end = (source_idx, target_len);
+                }
+            }
+        }
+
+        if self.mode.free_target_trailing {
+            for target_idx in 0..target_len {
+                if self.cost_matrix[(source_len, target_idx)] < self.cost_matrix[end] {
+                    end = (source_len, target_idx);
+                }
+            }
+        }
+
+        end
+    }
+
+    /// Get the alignment mode.
+    pub fn mode(&self) -> AlignmentMode {
+        self.mode
     }
 
     /// Return the script of edit operations to rewrite the source sequence
     /// to the target sequence. If there are multiple possible edit scripts,
     /// this method will return one of the possible edit scripts. If you want
     /// to retrieve all possible edit scripts, use the `edit_scripts` method.
+    ///
+    /// Panics when it is not possible to backtrack to cell (0, 0). Use
+    /// `try_edit_script` to get an error instead.
     pub fn edit_script(&self) -> Vec<IndexedOperation<M::Operation>> {
-        let mut source_idx = self.pair.source.len();
-        let mut target_idx = self.pair.target.len();
+        self.try_edit_script()
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Return the script of edit operations to rewrite the source sequence
+    /// to the target sequence.
+    ///
+    /// This method is the same as `edit_script`, but returns an error when
+    /// it is not possible to backtrack to cell (0, 0).
+    pub fn try_edit_script(&self) -> Result<Vec<IndexedOperation<M::Operation>>, AlignError> {
+        let (mut source_idx, mut target_idx) = self.end_cell();
         let mut script = Vec::new();
 
-        while let Some(op) =
-            self.measure
+        while !self.mode.is_start_cell(source_idx, target_idx) {
+            let cannot_backtrack = AlignError::CannotBacktrack {
+                source_idx,
+                target_idx,
+            };
+
+            let op = self
+                .measure
                 .backtrack(&self.pair, &self.cost_matrix, source_idx, target_idx)
-        {
+                .ok_or(cannot_backtrack)?;
             let (new_source_idx, new_target_idx) = op
                 .backtrack(&self.pair, source_idx, target_idx)
-                .expect("Cannot backtrack");
+                .ok_or(cannot_backtrack)?;
+
+            // Guard against operations that do not move towards cell (0, 0).
+            if (new_source_idx, new_target_idx) == (source_idx, target_idx) {
+                return Err(cannot_backtrack);
+            }
+
             source_idx = new_source_idx;
             target_idx = new_target_idx;
 
             script.push(IndexedOperation::new(op, source_idx, target_idx));
-
-            if source_idx == 0 && target_idx == 0 {
-                break;
-            }
         }
 
-        assert_eq!(source_idx, 0, "Cannot backtrack to cell 0, 0");
-        assert_eq!(target_idx, 0, "Cannot backtrack to cell 0, 0");
-
         script.reverse();
 
+        Ok(script)
+    }
+
+    /// Return the script of edit operations with the cost of each
+    /// operation.
+    ///
+    /// This method returns the same edit script as `edit_script`, pairing
+    /// every operation with the cost that it added to the edit distance.
+    /// This is useful for measures where the cost of an operation depends
+    /// on the sequence elements. The cost of an operation is found by
+    /// applying the operation to a cost matrix of zeros.
+    ///
+    /// Panics when it is not possible to backtrack to cell (0, 0).
+    pub fn edit_script_with_costs(&self) -> Vec<(IndexedOperation<M::Operation>, M::Cost)> {
+        let (rows, columns) = self.cost_matrix.shape();
+
+        // Matrix where every cell has cost zero, storing just one row.
+        let zeros = CostMatrix::with_stored_rows(rows, columns, 1);
+
+        let script = self.edit_script();
+
+        // Every operation is applied in the cell where the next operation
+        // starts, or in the end cell for the last operation.
+        let cells: Vec<_> = script
+            .iter()
+            .skip(1)
+            .map(|op| (op.source_idx(), op.target_idx()))
+            .chain(iter::once(self.end_cell()))
+            .collect();
+
         script
+            .into_iter()
+            .zip(cells)
+            .map(|(op, (source_idx, target_idx))| {
+                let cost = op
+                    .operation()
+                    .cost(&self.pair, &zeros, source_idx, target_idx)
+                    .expect("Operation in edit script cannot be applied");
+                (op, cost)
+            })
+            .collect()
     }
 
     /// Return all the edit scripts to rewrite the source sequence to the
     /// target sequence. If you want just one edit script, use the
     /// `edit_script` method instead.
+    ///
+    /// The number of edit scripts can grow exponentially in the sequence
+    /// lengths. Use the `edit_scripts_iter` method to get the edit scripts
+    /// one at a time.
     pub fn edit_scripts(&self) -> HashSet<Vec<IndexedOperation<M::Operation>>> {
-        // Find all scripts that lead to the lowest edit distance using
-        // breadth-first search.
-
-        // Start the search in the lower-right corner with the final cost.
-        let mut q: VecDeque<BacktrackState<M, T>> = VecDeque::new();
-        q.push_back(BacktrackState {
-            source_idx: self.pair.source.len(),
-            target_idx: self.pair.target.len(),
-            script: Vec::new(),
-        });
+        let scripts: HashSet<_> = self.edit_scripts_iter().collect();
 
-        let mut scripts = HashSet::new();
-        while let Some(BacktrackState {
-            source_idx,
-            target_idx,
-            script,
-        }) = q.pop_front()
-        {
-            // Process all operations/origins that can lead to the current cell's
-            // cost.
-            for op in self
-                .measure
-                .backtracks(&self.pair, &self.cost_matrix, source_idx, target_idx)
-            {
-                let (new_source_idx, new_target_idx) = op
-                    .backtrack(&self.pair, source_idx, target_idx)
-                    .expect("Cannot backtrack");
-                let mut new_script = script.clone();
-
-                new_script.push(IndexedOperation::new(op, new_source_idx, new_target_idx));
-
-                if new_source_idx == 0 && new_target_idx == 0 {
-                    // If we are in the upper-left cell, we have a complete script.
-                    new_script.reverse();
-                    scripts.insert(new_script);
-                } else {
-                    // Otherwise, add the state to the queue to explore later.
-                    q.push_back(BacktrackState {
-                        source_idx: new_source_idx,
-                        target_idx: new_target_idx,
-                        script: new_script,
-                    })
+        assert!(!scripts.is_empty(), "Cannot backtrack to cell 0, 0");
+
+        scripts
+    }
+
+    /// Return the `k` edit scripts with the lowest costs.
+    ///
+    /// This method returns the `k` lowest-cost edit scripts to rewrite the
+    /// source sequence to the target sequence, including edit scripts
+    /// with a cost above the edit distance. Each edit script is paired
+    /// with its cost and the edit scripts are sorted by increasing cost.
+    /// Fewer than `k` edit scripts are returned when there are fewer than
+    /// `k` possible edit scripts.
+    ///
+    /// The edit scripts are found with a best-first search from the cells
+    /// where an edit script can end, which are all the cells of the last
+    /// column and/or row when the alignment mode has free trailing
+    /// elements. The cost matrix gives the lowest cost to complete an
+    /// edit script. For alignments that were computed with
+    /// `Align::align_within`, costs above the maximum distance are not
+    /// computed, so the maximum distance is used as the lowest cost
+    /// instead. The cost of an operation is found by applying the
+    /// operation to a cost matrix of zeros, so the cost of an operation
+    /// must be the cost of the cell that it backtracks to plus the
+    /// operation's own cost.
+    pub fn k_best_edit_scripts(&self, k: usize) -> Vec<(EditScript<M::Operation>, M::Cost)> {
+        let (rows, columns) = self.cost_matrix.shape();
+
+        // Matrix where every cell has cost zero, storing just one row.
+        let zeros = CostMatrix::with_stored_rows(rows, columns, 1);
+
+        // Partial edit scripts are stored as linked lists, where every
+        // node points to the next operation in the script.
+        let mut nodes: Vec<(IndexedOperation<M::Operation>, Option<usize>)> = Vec::new();
+
+        let mut agenda = BinaryHeap::new();
+        let mut order = 0;
+        let mut empty_script = false;
+        for (source_idx, target_idx) in self.end_cells() {
+            // All start cells give the same empty edit script.
+            if self.mode.is_start_cell(source_idx, target_idx) {
+                if empty_script {
+                    continue;
                 }
+                empty_script = true;
             }
+
+            agenda.push(Reverse(KBestState {
+                estimate: self.min_cost(source_idx, target_idx),
+                order,
+                cost: M::Cost::zero(),
+                source_idx,
+                target_idx,
+                node: None,
+            }));
+            order += 1;
         }
 
-        assert!(!scripts.is_empty(), "Cannot backtrack to cell 0, 0");
+        let mut scripts = Vec::new();
+        while let Some(Reverse(state)) = agenda.pop() {
+            if scripts.len() == k {
+                break;
+            }
+
+            if self.mode.is_start_cell(state.source_idx, state.target_idx) {
+                let mut script = Vec::new();
+                let mut node = state.node;
+                while let Some(idx) = node {
+                    script.push(nodes[idx].0.clone());
+                    node = nodes[idx].1;
+                }
+
+                scripts.push((script, state.cost));
+                continue;
+            }
+
+            for op in self.measure.operations() {
+                let (from_source_idx, from_target_idx) =
+                    match op.backtrack(&self.pair, state.source_idx, state.target_idx) {
+                        Some(from) => from,
+                        None => continue,
+                    };
+
+                let op_cost = match op.cost(&self.pair, &zeros, state.source_idx, state.target_idx)
+                {
+                    Some(op_cost) => op_cost,
+                    None => continue,
+                };
+
+                nodes.push((
+                    IndexedOperation::new(op.clone(), from_source_idx, from_target_idx),
+                    state.node,
+                ));
+
+                let cost = state.cost.saturating_add(op_cost);
+                agenda.push(Reverse(KBestState {
+                    estimate: cost.saturating_add(self.min_cost(from_source_idx, from_target_idx)),
+                    order,
+                    cost,
+                    source_idx: from_source_idx,
+                    target_idx: from_target_idx,
+                    node: Some(nodes.len() - 1),
+                }));
+                order += 1;
+            }
+        }
 
         scripts
     }
 
+    /// Get the cells of the cost matrix where an edit script can end.
+    ///
+    /// The cells are in the same order as the tie-breaking of `end_cell`.
+    fn end_cells(&self) -> Vec<(usize, usize)> {
+        let source_len = self.pair.source.len();
+        let target_len = self.pair.target.len();
+
+        let mut cells = vec![(source_len, target_len)];
+        if self.mode.free_source_trailing {
+            cells.extend((0..source_len).map(|source_idx| (source_idx, target_len)));
+        }
+        if self.mode.free_target_trailing {
+            cells.extend((0..target_len).map(|target_idx| (source_len, target_idx)));
+        }
+
+        cells
+    }
+
+    /// Get a lower bound of the cost of aligning up to a cell.
+    ///
+    /// This is the cost of the cell, unless the alignment was computed
+    /// with `Align::align_within`. Then a cell with a cost above the
+    /// maximum distance may not have been computed (or was computed from
+    /// cells that were not computed), but its actual cost is known to
+    /// exceed the maximum distance.
+    fn min_cost(&self, source_idx: usize, target_idx: usize) -> M::Cost {
+        let cost = self.cost_matrix[(source_idx, target_idx)];
+        match self.max_distance {
+            Some(max_distance) => cost.min(max_distance),
+            None => cost,
+        }
+    }
+
+    /// Get the number of edit scripts to rewrite the source sequence to
+    /// the target sequence.
+    ///
+    /// This gives the same result as `edit_scripts().len()`, but counts
+    /// the edit scripts without constructing them. The count saturates at
+    /// `u128::MAX`.
+    pub fn num_optimal_scripts(&self) -> u128 {
+        let (rows, columns) = self.cost_matrix.shape();
+
+        // Number of paths from a start cell to every cell.
+        let mut counts = vec![0u128; rows * columns];
+
+        for source_idx in 0..rows {
+            for target_idx in 0..columns {
+                if self.mode.is_start_cell(source_idx, target_idx) {
+                    counts[source_idx * columns + target_idx] = 1;
+                    continue;
+                }
+
+                let mut count = 0u128;
+                for op in self.backtrack_ops(source_idx, target_idx) {
+                    count =
+                        count.saturating_add(counts[op.source_idx() * columns + op.target_idx()]);
+                }
+
+                counts[source_idx * columns + target_idx] = count;
+            }
+        }
+
+        let (source_idx, target_idx) = self.end_cell();
+        counts[source_idx * columns + target_idx]
+    }
+
+    /// Return an iterator over all the edit scripts to rewrite the source
+    /// sequence to the target sequence.
+    ///
+    /// The edit scripts are constructed lazily using depth-first
+    /// backtracking, so only the current edit script is kept in memory.
+    /// The edit scripts are returned in a deterministic order: at every
+    /// cell, the operations are tried in the order of
+    /// `Measure::operations`.
+    pub fn edit_scripts_iter(&self) -> EditScriptsIter<'_, 'a, M, S, U> {
+        let (source_idx, target_idx) = self.end_cell();
+
+        EditScriptsIter {
+            alignment: self,
+            stack: vec![self.backtrack_ops(source_idx, target_idx)],
+            script: Vec::new(),
+            empty: self.mode.is_start_cell(source_idx, target_idx),
+        }
+    }
+
+    /// Get the operations that lead to a cell's cost, in reverse order.
+    ///
+    /// Operations that cannot backtrack from the cell, or that backtrack
+    /// to the cell itself, cannot be part of an edit script and are
+    /// skipped.
+    fn backtrack_ops(
+        &self,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Vec<IndexedOperation<M::Operation>> {
+        let mut ops: Vec<_> = self
+            .measure
+            .backtracks(&self.pair, &self.cost_matrix, source_idx, target_idx)
+            .into_iter()
+            .filter_map(|op| {
+                let (from_source_idx, from_target_idx) =
+                    op.backtrack(&self.pair, source_idx, target_idx)?;
+                if (from_source_idx, from_target_idx) == (source_idx, target_idx) {
+                    return None;
+                }
+
+                Some(IndexedOperation::new(op, from_source_idx, from_target_idx))
+            })
+            .collect();
+        ops.reverse();
+        ops
+    }
+
     /// Get the cost matrix.
-    pub fn cost_matrix(&self) -> &Vec<Vec<usize>> {
+    pub fn cost_matrix(&self) -> &CostMatrix<M::Cost> {
         &self.cost_matrix
     }
 
     /// Get the sequence pair associated with this cost matrix.
-    pub fn seq_pair(&self) -> &SeqPair<T> {
+    pub fn seq_pair(&self) -> &SeqPair<'_, S, U> {
         &self.pair
     }
 }
 
-struct BacktrackState<M, T>
+/// Iterator over edit scripts.
+///
+/// This iterator is created by `Alignment::edit_scripts_iter`.
+pub struct EditScriptsIter<'b, 'a, M, S, U = S>
+where
+    M: Measure<S, U>,
+{
+    alignment: &'b Alignment<'a, M, S, U>,
+
+    // Operations that remain to be explored for each cell on the current
+    // path, starting at the lower-right cell.
+    stack: Vec<Vec<IndexedOperation<M::Operation>>>,
+
+    // The edit script of the current path, in reverse order.
+    script: Vec<IndexedOperation<M::Operation>>,
+
+    // Aligning two empty sequences gives one empty edit script.
+    empty: bool,
+}
+
+impl<'b, 'a, M, S, U> Iterator for EditScriptsIter<'b, 'a, M, S, U>
 where
-    M: Measure<T>,
+    M: Measure<S, U>,
 {
+    type Item = Vec<IndexedOperation<M::Operation>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.empty {
+            self.empty = false;
+            self.stack.clear();
+            return Some(Vec::new());
+        }
+
+        loop {
+            let ops = self.stack.last_mut()?;
+
+            let op = match ops.pop() {
+                Some(op) => op,
+                None => {
+                    // All operations of this cell are explored, go back to
+                    // the cell that we came from.
+                    self.stack.pop();
+                    self.script.pop();
+                    continue;
+                }
+            };
+
+            let (source_idx, target_idx) = (op.source_idx(), op.target_idx());
+            self.script.push(op);
+
+            if self.alignment.mode.is_start_cell(source_idx, target_idx) {
+                // If we are in a start cell, we have a complete script.
+                let script = self.script.iter().rev().cloned().collect();
+                self.script.pop();
+                return Some(script);
+            }
+
+            self.stack
+                .push(self.alignment.backtrack_ops(source_idx, target_idx));
+        }
+    }
+}
+
+/// Partial edit script in the k-best search.
+///
+/// States are ordered by the estimated cost of the complete edit script.
+/// Ties are broken by the order in which states were created, to give
+/// deterministic results.
+#[derive(Eq, Ord, PartialEq, PartialOrd)]
+struct KBestState<C> {
+    estimate: C,
+    order: usize,
+    cost: C,
     source_idx: usize,
     target_idx: usize,
-    script: Vec<IndexedOperation<M::Operation>>,
+    node: Option<usize>,
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+
+    use crate::equivalence::{Equal, EquivalenceFn};
     use crate::measures::Levenshtein;
+    use crate::measures::LevenshteinDamerau;
     use crate::measures::LevenshteinOp::*;
-    use crate::op::IndexedOperation;
+    use crate::measures::LCS;
+    use crate::op::{archetype, IndexedOperation, Operation};
+    use crate::{AlignError, AlignmentMode, CostMatrix, Measure, SeqPair};
 
     use super::Align;
 
+    /// Measure that can only match elements.
+    struct MatchOnly([archetype::Match; 1]);
+
+    impl<T> Measure<T> for MatchOnly
+    where
+        T: Eq,
+    {
+        type Cost = usize;
+
+        type Operation = archetype::Match;
+
+        fn operations(&self) -> &[Self::Operation] {
+            &self.0
+        }
+    }
+
+    /// Operation that can be applied everywhere, but cannot be backtracked.
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    struct Teleport;
+
+    impl<T> Operation<T> for Teleport {
+        fn backtrack(
+            &self,
+            _seq_pair: &SeqPair<T>,
+            _source_idx: usize,
+            _target_idx: usize,
+        ) -> Option<(usize, usize)> {
+            None
+        }
+
+        fn cost(
+            &self,
+            _seq_pair: &SeqPair<T>,
+            _cost_matrix: &CostMatrix,
+            _source_idx: usize,
+            _target_idx: usize,
+        ) -> Option<usize> {
+            Some(0)
+        }
+    }
+
+    struct TeleportOnly([Teleport; 1]);
+
+    impl<T> Measure<T> for TeleportOnly
+    where
+        T: Eq,
+    {
+        type Cost = usize;
+
+        type Operation = Teleport;
+
+        fn operations(&self) -> &[Self::Operation] {
+            &self.0
+        }
+    }
+
+    /// Levenshtein measure for byte sources and character targets.
+    struct ByteCharLevenshtein([ByteCharOp; 4]);
+
+    impl ByteCharLevenshtein {
+        fn new() -> Self {
+            let equivalence =
+                EquivalenceFn::new(|&b: &u8, c: &char| c.eq_ignore_ascii_case(&(b as char)));
+            ByteCharLevenshtein([
+                ByteCharOp::Insert,
+                ByteCharOp::Delete,
+                ByteCharOp::Match(equivalence),
+                ByteCharOp::Substitute,
+            ])
+        }
+    }
+
+    impl Measure<u8, char> for ByteCharLevenshtein {
+        type Cost = usize;
+
+        type Operation = ByteCharOp;
+
+        fn operations(&self) -> &[Self::Operation] {
+            &self.0
+        }
+    }
+
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    enum ByteCharOp {
+        Insert,
+        Delete,
+        Match(EquivalenceFn<u8, char>),
+        Substitute,
+    }
+
+    impl Operation<u8, usize, char> for ByteCharOp {
+        fn backtrack(
+            &self,
+            seq_pair: &SeqPair<u8, char>,
+            source_idx: usize,
+            target_idx: usize,
+        ) -> Option<(usize, usize)> {
+            match self {
+                ByteCharOp::Insert => {
+                    archetype::Insert(1usize).backtrack(seq_pair, source_idx, target_idx)
+                }
+                ByteCharOp::Delete => {
+                    archetype::Delete(1usize).backtrack(seq_pair, source_idx, target_idx)
+                }
+                ByteCharOp::Match(equivalence) => Operation::<u8, usize, char>::backtrack(
+                    &archetype::MatchWith(equivalence),
+                    seq_pair,
+                    source_idx,
+                    target_idx,
+                ),
+                ByteCharOp::Substitute => {
+                    archetype::Substitute(1usize).backtrack(seq_pair, source_idx, target_idx)
+                }
+            }
+        }
+
+        fn cost(
+            &self,
+            seq_pair: &SeqPair<u8, char>,
+            cost_matrix: &CostMatrix,
+            source_idx: usize,
+            target_idx: usize,
+        ) -> Option<usize> {
+            match self {
+                ByteCharOp::Insert => {
+                    archetype::Insert(1).cost(seq_pair, cost_matrix, source_idx, target_idx)
+                }
+                ByteCharOp::Delete => {
+                    archetype::Delete(1).cost(seq_pair, cost_matrix, source_idx, target_idx)
+                }
+                ByteCharOp::Match(equivalence) => archetype::MatchWith(equivalence).cost(
+                    seq_pair,
+                    cost_matrix,
+                    source_idx,
+                    target_idx,
+                ),
+                ByteCharOp::Substitute => {
+                    archetype::Substitute(1).cost(seq_pair, cost_matrix, source_idx, target_idx)
+                }
+            }
+        }
+    }
+
     #[test]
     fn distance_test() {
         let applet: Vec<char> = "applet".chars().collect();
@@ -208,6 +1035,130 @@ mod tests {
         assert_eq!(levenshtein.align(&applet, &pen).distance(), 4);
     }
 
+    #[test]
+    fn distance_only_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+        let pen: Vec<char> = "pen".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let levenshtein_damerau = LevenshteinDamerau::new(1, 1, 1, 1);
+
+        for (source, target) in &[
+            (&pineapple, &pen),
+            (&pen, &pineapple),
+            (&pineapple, &applet),
+            (&applet, &aplpet),
+        ] {
+            assert_eq!(
+                levenshtein.align(source, target).distance(),
+                levenshtein.distance(source, target)
+            );
+            assert_eq!(
+                levenshtein_damerau.align(source, target).distance(),
+                levenshtein_damerau.distance(source, target)
+            );
+        }
+
+        assert_eq!(levenshtein_damerau.distance(&applet, &aplpet), 1);
+    }
+
+    #[test]
+    fn align_within_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+        let pen: Vec<char> = "pen".chars().collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        assert!(levenshtein.align_within(&pineapple, &pen, 6).is_none());
+        assert!(levenshtein.align_within(&pen, &pineapple, 6).is_none());
+        assert!(levenshtein.align_within(&pineapple, &applet, 0).is_none());
+
+        let alignment = levenshtein.align_within(&pineapple, &pen, 7).unwrap();
+        assert_eq!(alignment.distance(), 7);
+        assert_eq!(
+            alignment.edit_script(),
+            levenshtein.align(&pineapple, &pen).edit_script()
+        );
+
+        let alignment = levenshtein.align_within(&pineapple, &applet, 10).unwrap();
+        assert_eq!(alignment.distance(), 5);
+        assert_eq!(
+            alignment.edit_scripts(),
+            levenshtein.align(&pineapple, &applet).edit_scripts()
+        );
+
+        let alignment = levenshtein.align_within(&applet, &applet, 0).unwrap();
+        assert_eq!(alignment.distance(), 0);
+    }
+
+    #[test]
+    fn try_align_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+
+        let match_only = MatchOnly([archetype::Match]);
+        assert_eq!(
+            match_only.try_align(&applet, &aplpet).err(),
+            Some(AlignError::NoApplicableOperation {
+                source_idx: 0,
+                target_idx: 1
+            })
+        );
+
+        assert_eq!(
+            match_only.try_align_within(&applet, &aplpet, 2).err(),
+            Some(AlignError::NoApplicableOperation {
+                source_idx: 0,
+                target_idx: 1
+            })
+        );
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        assert_eq!(
+            levenshtein
+                .try_align_within(&applet, &aplpet, 1)
+                .map(|alignment| alignment.is_none()),
+            Ok(true)
+        );
+        assert_eq!(
+            levenshtein
+                .try_align(&applet, &aplpet)
+                .unwrap()
+                .try_edit_script(),
+            Ok(levenshtein.align(&applet, &aplpet).edit_script())
+        );
+    }
+
+    #[test]
+    fn try_edit_script_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+
+        let teleport_only = TeleportOnly([Teleport]);
+        let alignment = teleport_only.try_align(&applet, &aplpet).unwrap();
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(
+            alignment.try_edit_script(),
+            Err(AlignError::CannotBacktrack {
+                source_idx: 6,
+                target_idx: 6
+            })
+        );
+        assert_eq!(alignment.edit_scripts_iter().next(), None);
+        assert_eq!(alignment.num_optimal_scripts(), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "No applicable operation for cell (0, 1)")]
+    fn align_panic_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+        MatchOnly([archetype::Match]).align(&applet, &aplpet);
+    }
+
     #[test]
     fn edit_script_test() {
         let applet: Vec<char> = "applet".chars().collect();
@@ -218,9 +1169,9 @@ mod tests {
 
         assert_eq!(
             vec![
-                IndexedOperation::new(Match, 0, 0),
+                IndexedOperation::new(Match(Equal), 0, 0),
                 IndexedOperation::new(Substitute(1), 1, 1),
-                IndexedOperation::new(Match, 2, 2),
+                IndexedOperation::new(Match(Equal), 2, 2),
                 IndexedOperation::new(Delete(1), 3, 3),
                 IndexedOperation::new(Delete(1), 4, 3),
                 IndexedOperation::new(Delete(1), 5, 3),
@@ -233,9 +1184,9 @@ mod tests {
 
         assert_eq!(
             vec![
-                IndexedOperation::new(Match, 0, 0),
+                IndexedOperation::new(Match(Equal), 0, 0),
                 IndexedOperation::new(Substitute(1), 1, 1),
-                IndexedOperation::new(Match, 2, 2),
+                IndexedOperation::new(Match(Equal), 2, 2),
                 IndexedOperation::new(Insert(1), 3, 3),
                 IndexedOperation::new(Insert(1), 3, 4),
                 IndexedOperation::new(Insert(1), 3, 5),
@@ -252,11 +1203,11 @@ mod tests {
                 IndexedOperation::new(Delete(1), 1, 0),
                 IndexedOperation::new(Delete(1), 2, 0),
                 IndexedOperation::new(Delete(1), 3, 0),
-                IndexedOperation::new(Match, 4, 0),
-                IndexedOperation::new(Match, 5, 1),
-                IndexedOperation::new(Match, 6, 2),
-                IndexedOperation::new(Match, 7, 3),
-                IndexedOperation::new(Match, 8, 4),
+                IndexedOperation::new(Match(Equal), 4, 0),
+                IndexedOperation::new(Match(Equal), 5, 1),
+                IndexedOperation::new(Match(Equal), 6, 2),
+                IndexedOperation::new(Match(Equal), 7, 3),
+                IndexedOperation::new(Match(Equal), 8, 4),
                 IndexedOperation::new(Insert(1), 9, 5),
             ],
             levenshtein.align(&pineapple, &applet).edit_script()
@@ -276,11 +1227,11 @@ mod tests {
                 IndexedOperation::new(Delete(1), 1, 0),
                 IndexedOperation::new(Delete(1), 2, 0),
                 IndexedOperation::new(Delete(1), 3, 0),
-                IndexedOperation::new(Match, 4, 0),
-                IndexedOperation::new(Match, 5, 1),
-                IndexedOperation::new(Match, 6, 2),
-                IndexedOperation::new(Match, 7, 3),
-                IndexedOperation::new(Match, 8, 4),
+                IndexedOperation::new(Match(Equal), 4, 0),
+                IndexedOperation::new(Match(Equal), 5, 1),
+                IndexedOperation::new(Match(Equal), 6, 2),
+                IndexedOperation::new(Match(Equal), 7, 3),
+                IndexedOperation::new(Match(Equal), 8, 4),
                 IndexedOperation::new(Insert(1), 9, 5),
             ],],
             levenshtein.align(&pineapple, &applet).edit_scripts()
@@ -289,39 +1240,39 @@ mod tests {
         assert_eq!(
             hashset![
                 vec![
-                    IndexedOperation::new(Match, 0, 0),
-                    IndexedOperation::new(Match, 1, 1),
+                    IndexedOperation::new(Match(Equal), 0, 0),
+                    IndexedOperation::new(Match(Equal), 1, 1),
                     IndexedOperation::new(Substitute(1), 2, 2),
                     IndexedOperation::new(Substitute(1), 3, 3),
-                    IndexedOperation::new(Match, 4, 4),
-                    IndexedOperation::new(Match, 5, 5),
+                    IndexedOperation::new(Match(Equal), 4, 4),
+                    IndexedOperation::new(Match(Equal), 5, 5),
                 ],
                 vec![
-                    IndexedOperation::new(Match, 0, 0),
-                    IndexedOperation::new(Match, 1, 1),
+                    IndexedOperation::new(Match(Equal), 0, 0),
+                    IndexedOperation::new(Match(Equal), 1, 1),
                     IndexedOperation::new(Delete(1), 2, 2),
-                    IndexedOperation::new(Match, 3, 2),
+                    IndexedOperation::new(Match(Equal), 3, 2),
                     IndexedOperation::new(Insert(1), 4, 3),
-                    IndexedOperation::new(Match, 4, 4),
-                    IndexedOperation::new(Match, 5, 5),
+                    IndexedOperation::new(Match(Equal), 4, 4),
+                    IndexedOperation::new(Match(Equal), 5, 5),
                 ],
                 vec![
-                    IndexedOperation::new(Match, 0, 0),
+                    IndexedOperation::new(Match(Equal), 0, 0),
                     IndexedOperation::new(Delete(1), 1, 1),
-                    IndexedOperation::new(Match, 2, 1),
-                    IndexedOperation::new(Match, 3, 2),
+                    IndexedOperation::new(Match(Equal), 2, 1),
+                    IndexedOperation::new(Match(Equal), 3, 2),
                     IndexedOperation::new(Insert(1), 4, 3),
-                    IndexedOperation::new(Match, 4, 4),
-                    IndexedOperation::new(Match, 5, 5),
+                    IndexedOperation::new(Match(Equal), 4, 4),
+                    IndexedOperation::new(Match(Equal), 5, 5),
                 ],
                 vec![
-                    IndexedOperation::new(Match, 0, 0),
-                    IndexedOperation::new(Match, 1, 1),
+                    IndexedOperation::new(Match(Equal), 0, 0),
+                    IndexedOperation::new(Match(Equal), 1, 1),
                     IndexedOperation::new(Insert(1), 2, 2),
-                    IndexedOperation::new(Match, 2, 3),
+                    IndexedOperation::new(Match(Equal), 2, 3),
                     IndexedOperation::new(Delete(1), 3, 4),
-                    IndexedOperation::new(Match, 4, 4),
-                    IndexedOperation::new(Match, 5, 5),
+                    IndexedOperation::new(Match(Equal), 4, 4),
+                    IndexedOperation::new(Match(Equal), 5, 5),
                 ],
             ],
             levenshtein.align(&applet, &aplpet).edit_scripts()
@@ -329,17 +1280,239 @@ mod tests {
 
         assert_eq!(
             hashset![vec![
-                IndexedOperation::new(Match, 0, 0),
-                IndexedOperation::new(Match, 1, 1),
-                IndexedOperation::new(Match, 2, 2),
-                IndexedOperation::new(Match, 3, 3),
-                IndexedOperation::new(Match, 4, 4),
-                IndexedOperation::new(Match, 5, 5),
+                IndexedOperation::new(Match(Equal), 0, 0),
+                IndexedOperation::new(Match(Equal), 1, 1),
+                IndexedOperation::new(Match(Equal), 2, 2),
+                IndexedOperation::new(Match(Equal), 3, 3),
+                IndexedOperation::new(Match(Equal), 4, 4),
+                IndexedOperation::new(Match(Equal), 5, 5),
             ]],
             levenshtein.align(&applet, &applet).edit_scripts()
         );
     }
 
+    #[test]
+    fn edit_scripts_iter_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let alignment = levenshtein.align(&applet, &aplpet);
+
+        let scripts: Vec<_> = alignment.edit_scripts_iter().collect();
+        assert_eq!(scripts.len(), 4);
+        assert_eq!(
+            scripts.iter().cloned().collect::<HashSet<_>>(),
+            alignment.edit_scripts()
+        );
+
+        // Operations are explored in the order of the measure's operations.
+        assert_eq!(
+            scripts[0],
+            vec![
+                IndexedOperation::new(Match(Equal), 0, 0),
+                IndexedOperation::new(Match(Equal), 1, 1),
+                IndexedOperation::new(Delete(1), 2, 2),
+                IndexedOperation::new(Match(Equal), 3, 2),
+                IndexedOperation::new(Insert(1), 4, 3),
+                IndexedOperation::new(Match(Equal), 4, 4),
+                IndexedOperation::new(Match(Equal), 5, 5),
+            ]
+        );
+        assert_eq!(
+            alignment.edit_scripts_iter().take(2).collect::<Vec<_>>(),
+            &scripts[..2]
+        );
+    }
+
+    #[test]
+    fn edit_scripts_iter_repetitive_test() {
+        let source = vec!['a'; 40];
+        let target = vec!['a'; 38];
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let alignment = levenshtein.align(&source, &target);
+
+        let mut scripts = alignment.edit_scripts_iter();
+        let script = scripts.next().unwrap();
+        assert_eq!(script.len(), 40);
+        assert_eq!(
+            &script[38..],
+            &[
+                IndexedOperation::new(Delete(1), 38, 38),
+                IndexedOperation::new(Delete(1), 39, 38)
+            ]
+        );
+        assert_eq!(scripts.take(99).count(), 99);
+    }
+
+    #[test]
+    fn num_optimal_scripts_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        for (source, target) in &[
+            (&applet, &aplpet),
+            (&pineapple, &applet),
+            (&applet, &pineapple),
+            (&applet, &applet),
+        ] {
+            let alignment = levenshtein.align(source, target);
+            assert_eq!(
+                alignment.num_optimal_scripts(),
+                alignment.edit_scripts().len() as u128
+            );
+        }
+
+        let source = vec!['a'; 40];
+        let target = vec!['a'; 38];
+        assert_eq!(
+            levenshtein.align(&source, &target).num_optimal_scripts(),
+            40 * 39 / 2
+        );
+
+        let empty: &[char] = &[];
+        assert_eq!(levenshtein.align(empty, empty).num_optimal_scripts(), 1);
+    }
+
+    #[test]
+    fn num_optimal_scripts_saturate_test() {
+        let lcs = LCS::new(1, 1);
+
+        // Every interleaving of the deletions and insertions is optimal.
+        let source = vec!['a'; 10];
+        let target = vec!['b'; 10];
+        assert_eq!(lcs.align(&source, &target).num_optimal_scripts(), 184_756);
+
+        let source = vec!['a'; 70];
+        let target = vec!['b'; 70];
+        assert_eq!(
+            lcs.align(&source, &target).num_optimal_scripts(),
+            u128::max_value()
+        );
+    }
+
+    #[test]
+    fn k_best_edit_scripts_test() {
+        let applet: Vec<char> = "applet".chars().collect();
+        let aplpet: Vec<char> = "aplpet".chars().collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let alignment = levenshtein.align(&applet, &aplpet);
+
+        let k_best = alignment.k_best_edit_scripts(10);
+        assert_eq!(k_best.len(), 10);
+
+        let costs: Vec<_> = k_best.iter().map(|(_, cost)| *cost).collect();
+        assert_eq!(costs, vec![2, 2, 2, 2, 3, 3, 3, 3, 3, 3]);
+
+        // The lowest-cost edit scripts are the optimal edit scripts.
+        assert_eq!(
+            k_best[..4]
+                .iter()
+                .map(|(script, _)| script.clone())
+                .collect::<HashSet<_>>(),
+            alignment.edit_scripts()
+        );
+
+        // All edit scripts are distinct.
+        assert_eq!(
+            k_best
+                .iter()
+                .map(|(script, _)| script.clone())
+                .collect::<HashSet<_>>()
+                .len(),
+            10
+        );
+
+        // The edit scripts are complete.
+        for (script, _) in &k_best {
+            let last = script.last().unwrap();
+            assert!(last.source_idx() == 5 || last.target_idx() == 5);
+        }
+    }
+
+    #[test]
+    fn k_best_edit_scripts_within_test() {
+        let abc: Vec<char> = "abc".chars().collect();
+
+        // Only the diagonal is within the maximum distance. Cells outside
+        // the band do not have lower costs than the cells in the band, so
+        // the k-best search gives the same edit scripts in the same cost
+        // order as with the full cost matrix.
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let within = levenshtein
+            .align_within(&abc, &abc, 0)
+            .unwrap()
+            .k_best_edit_scripts(usize::max_value());
+        let full = levenshtein
+            .align(&abc, &abc)
+            .k_best_edit_scripts(usize::max_value());
+
+        assert_eq!(
+            within.iter().map(|(_, cost)| *cost).collect::<Vec<_>>(),
+            full.iter().map(|(_, cost)| *cost).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            within.into_iter().collect::<HashSet<_>>(),
+            full.into_iter().collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn k_best_edit_scripts_mode_test() {
+        let ab: Vec<char> = "ab".chars().collect();
+        let abxab: Vec<char> = "abxab".chars().collect();
+
+        // Both occurrences of the source are found, even though the edit
+        // scripts end in different cells.
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let alignment = levenshtein.align_with_mode(&ab, &abxab, AlignmentMode::source_in_target());
+        assert_eq!(
+            alignment.k_best_edit_scripts(2),
+            vec![
+                (
+                    vec![
+                        IndexedOperation::new(Match(Equal), 0, 3),
+                        IndexedOperation::new(Match(Equal), 1, 4)
+                    ],
+                    0
+                ),
+                (
+                    vec![
+                        IndexedOperation::new(Match(Equal), 0, 0),
+                        IndexedOperation::new(Match(Equal), 1, 1)
+                    ],
+                    0
+                ),
+            ]
+        );
+
+        // Every cell of the last row is a start cell, but there is only
+        // one empty edit script.
+        let alignment = levenshtein.align_with_mode(&[], &abxab, AlignmentMode::source_in_target());
+        assert_eq!(alignment.k_best_edit_scripts(3), vec![(vec![], 0)]);
+    }
+
+    #[test]
+    fn k_best_edit_scripts_exhausted_test() {
+        let empty: &[char] = &[];
+        let a = &['a'];
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        assert_eq!(
+            levenshtein.align(empty, a).k_best_edit_scripts(3),
+            vec![(vec![IndexedOperation::new(Insert(1), 0, 0)], 1)]
+        );
+        assert_eq!(
+            levenshtein.align(empty, empty).k_best_edit_scripts(3),
+            vec![(vec![], 0)]
+        );
+        assert!(levenshtein.align(a, a).k_best_edit_scripts(0).is_empty());
+    }
+
     #[test]
     fn align_empty_test() {
         let empty: &[char] = &[];
@@ -350,5 +1523,132 @@ mod tests {
         assert_eq!(levenshtein.align(empty, empty).distance(), 0);
         assert_eq!(levenshtein.align(non_empty.as_slice(), empty).distance(), 5);
         assert_eq!(levenshtein.align(empty, non_empty.as_slice()).distance(), 5);
+
+        assert_eq!(levenshtein.distance(empty, empty), 0);
+
+        assert_eq!(
+            levenshtein
+                .align(empty, empty)
+                .edit_scripts_iter()
+                .collect::<Vec<_>>(),
+            vec![vec![]]
+        );
+        assert_eq!(levenshtein.distance(non_empty.as_slice(), empty), 5);
+        assert_eq!(levenshtein.distance(empty, non_empty.as_slice()), 5);
+    }
+
+    #[test]
+    fn align_with_mode_test() {
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+        let apple: Vec<char> = "apple".chars().collect();
+        let aple: Vec<char> = "aple".chars().collect();
+        let pine: Vec<char> = "pine".chars().collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        let global = levenshtein.align_with_mode(&apple, &pineapple, AlignmentMode::global());
+        assert_eq!(global.distance(), 4);
+        assert_eq!(global.end_cell(), (5, 9));
+        assert_eq!(
+            global.edit_script(),
+            levenshtein.align(&apple, &pineapple).edit_script()
+        );
+
+        let alignment =
+            levenshtein.align_with_mode(&apple, &pineapple, AlignmentMode::source_in_target());
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(alignment.end_cell(), (5, 9));
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(Match(Equal), 0, 4),
+                IndexedOperation::new(Match(Equal), 1, 5),
+                IndexedOperation::new(Match(Equal), 2, 6),
+                IndexedOperation::new(Match(Equal), 3, 7),
+                IndexedOperation::new(Match(Equal), 4, 8),
+            ]
+        );
+        assert_eq!(alignment.num_optimal_scripts(), 1);
+        assert_eq!(
+            alignment.edit_scripts_iter().collect::<Vec<_>>(),
+            vec![alignment.edit_script()]
+        );
+        assert_eq!(
+            alignment.k_best_edit_scripts(1),
+            vec![(alignment.edit_script(), 0)]
+        );
+
+        let alignment =
+            levenshtein.align_with_mode(&aple, &pineapple, AlignmentMode::source_in_target());
+        assert_eq!(alignment.distance(), 1);
+
+        let alignment =
+            levenshtein.align_with_mode(&pineapple, &apple, AlignmentMode::target_in_source());
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(
+            alignment.edit_script()[0],
+            IndexedOperation::new(Match(Equal), 4, 0)
+        );
+
+        // Prefix: only trailing target elements are free.
+        let prefix = AlignmentMode {
+            free_target_trailing: true,
+            ..AlignmentMode::default()
+        };
+        let alignment = levenshtein.align_with_mode(&pine, &pineapple, prefix);
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(alignment.end_cell(), (4, 4));
+        assert_eq!(
+            levenshtein
+                .align_with_mode(&apple, &pineapple, prefix)
+                .distance(),
+            3
+        );
+
+        // Suffix: only leading source elements are free.
+        let suffix = AlignmentMode {
+            free_source_leading: true,
+            ..AlignmentMode::default()
+        };
+        let alignment = levenshtein.align_with_mode(&pineapple, &apple, suffix);
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(alignment.edit_script().len(), 5);
+        assert_eq!(
+            levenshtein
+                .align_with_mode(&pineapple, &pine, suffix)
+                .distance(),
+            2
+        );
+
+        let alignment = levenshtein.align_with_mode(&[], &apple, AlignmentMode::source_in_target());
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(
+            alignment.edit_scripts_iter().collect::<Vec<_>>(),
+            vec![vec![]]
+        );
+    }
+
+    #[test]
+    fn different_element_types_test() {
+        let measure = ByteCharLevenshtein::new();
+        let source = b"tpyo";
+        let target: Vec<char> = "TYPOS".chars().collect();
+
+        let alignment = measure.align(source, &target);
+        assert_eq!(alignment.distance(), 3);
+        assert_eq!(measure.distance(source, &target), 3);
+
+        let matched = measure.0[2].clone();
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(matched.clone(), 0, 0),
+                IndexedOperation::new(ByteCharOp::Delete, 1, 1),
+                IndexedOperation::new(matched.clone(), 2, 1),
+                IndexedOperation::new(ByteCharOp::Insert, 3, 2),
+                IndexedOperation::new(matched, 3, 3),
+                IndexedOperation::new(ByteCharOp::Insert, 4, 4),
+            ]
+        );
     }
 }
@@ -0,0 +1,291 @@
+//! Element equivalence.
+//!
+//! This module provides the `Equivalence` trait, which is used by the
+//! archetypal match and transpose operations and the measures in
+//! `measures` to decide whether two sequence elements are the same.
+//! `Equal` compares elements using `Eq`.
+//! `EquivalenceFn` and `KeyEquivalence` make it possible to match
+//! elements case-insensitively, with a tolerance, or by a key, without
+//! wrapping the elements in a newtype.
+
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Trait for equivalence relations on sequence elements.
+///
+/// The type parameters `S` and `U` are the element types of the source
+/// and target sequences. An equivalence between elements of different
+/// types can be used to align sequences with different element types.
+///
+/// Equivalences are used by `archetype::MatchWith`,
+/// `archetype::TransposeWith`, and `archetype::UnrestrictedTransposeWith`
+/// and can be set on `Levenshtein`, `LevenshteinDamerau`,
+/// `UnrestrictedLevenshteinDamerau`, `LCS`, `AffineGap`, and `Hamming`
+/// with their `with_equivalence` methods. `WeightedLevenshtein` and
+/// `MatrixLevenshtein` compare elements using `Eq`. `PrefixAligner`
+/// requires target elements to be `Eq` regardless of the equivalence,
+/// since it compares each target with the previous target to find their
+/// common prefix.
+pub trait Equivalence<S, U = S> {
+    /// Check whether two elements are equivalent.
+    fn equivalent(&self, a: &S, b: &U) -> bool;
+}
+
+impl<S, U, E> Equivalence<S, U> for &E
+where
+    E: Equivalence<S, U>,
+{
+    fn equivalent(&self, a: &S, b: &U) -> bool {
+        (*self).equivalent(a, b)
+    }
+}
+
+/// Equivalence of elements that are equal according to `Eq`.
+///
+/// This is the equivalence that is used by `archetype::Match`,
+/// `archetype::Transpose`, and `archetype::UnrestrictedTranspose`, and the
+/// default equivalence of the measures in `measures`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Equal;
+
+impl<T> Equivalence<T> for Equal
+where
+    T: Eq,
+{
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        a == b
+    }
+}
+
+type EquivalentFn<S, U> = Arc<dyn Fn(&S, &U) -> bool + Send + Sync>;
+
+/// Equivalence given by a closure.
+///
+/// Edit operations must be comparable and hashable, which closures are
+/// not. `EquivalenceFn` compares and hashes the closure by identity, so
+/// two `EquivalenceFn`s are only equal when one is a clone of the other.
+///
+/// ```
+/// use seqalign::equivalence::{Equivalence, EquivalenceFn};
+///
+/// let tolerance = EquivalenceFn::new(|a: &f64, b: &f64| (a - b).abs() < 0.1);
+/// assert!(tolerance.equivalent(&1.0, &1.05));
+/// assert!(!tolerance.equivalent(&1.0, &1.2));
+/// ```
+pub struct EquivalenceFn<S, U = S> {
+    equivalent: EquivalentFn<S, U>,
+}
+
+impl<S, U> EquivalenceFn<S, U> {
+    /// Construct an equivalence from a closure that checks whether two
+    /// elements are equivalent.
+    pub fn new<F>(equivalent: F) -> Self
+    where
+        F: Fn(&S, &U) -> bool + Send + Sync + 'static,
+    {
+        EquivalenceFn {
+            equivalent: Arc::new(equivalent),
+        }
+    }
+}
+
+impl<S, U> Clone for EquivalenceFn<S, U> {
+    fn clone(&self) -> Self {
+        EquivalenceFn {
+            equivalent: self.equivalent.clone(),
+        }
+    }
+}
+
+impl<S, U> Debug for EquivalenceFn<S, U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("EquivalenceFn")
+            .field("equivalent", &(&*self.equivalent as *const _ as *const ()))
+            .finish()
+    }
+}
+
+impl<S, U> Equivalence<S, U> for EquivalenceFn<S, U> {
+    fn equivalent(&self, a: &S, b: &U) -> bool {
+        (self.equivalent)(a, b)
+    }
+}
+
+impl<S, U> Eq for EquivalenceFn<S, U> {}
+
+impl<S, U> Hash for EquivalenceFn<S, U> {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        (&*self.equivalent as *const _ as *const ()).hash(state);
+    }
+}
+
+impl<S, U> PartialEq for EquivalenceFn<S, U> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.equivalent, &other.equivalent)
+    }
+}
+
+/// Equivalence of elements with equal keys.
+///
+/// Two elements are equivalent when the key extraction function gives
+/// equal keys for them. Like `EquivalenceFn`, the function is compared
+/// and hashed by identity.
+///
+/// ```
+/// use seqalign::equivalence::{Equivalence, KeyEquivalence};
+///
+/// let case_insensitive = KeyEquivalence::new(|c: &char| c.to_ascii_lowercase());
+/// assert!(case_insensitive.equivalent(&'a', &'A'));
+/// assert!(!case_insensitive.equivalent(&'a', &'B'));
+/// ```
+pub struct KeyEquivalence<T, K> {
+    key: Arc<dyn Fn(&T) -> K + Send + Sync>,
+}
+
+impl<T, K> KeyEquivalence<T, K> {
+    /// Construct an equivalence from a key extraction function.
+    pub fn new<F>(key: F) -> Self
+    where
+        F: Fn(&T) -> K + Send + Sync + 'static,
+    {
+        KeyEquivalence { key: Arc::new(key) }
+    }
+}
+
+impl<T, K> Clone for KeyEquivalence<T, K> {
+    fn clone(&self) -> Self {
+        KeyEquivalence {
+            key: self.key.clone(),
+        }
+    }
+}
+
+impl<T, K> Debug for KeyEquivalence<T, K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("KeyEquivalence")
+            .field("key", &(&*self.key as *const _ as *const ()))
+            .finish()
+    }
+}
+
+impl<T, K> Equivalence<T> for KeyEquivalence<T, K>
+where
+    K: Eq,
+{
+    fn equivalent(&self, a: &T, b: &T) -> bool {
+        (self.key)(a) == (self.key)(b)
+    }
+}
+
+impl<T, K> Eq for KeyEquivalence<T, K> {}
+
+impl<T, K> Hash for KeyEquivalence<T, K> {
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        (&*self.key as *const _ as *const ()).hash(state);
+    }
+}
+
+impl<T, K> PartialEq for KeyEquivalence<T, K> {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.key, &other.key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::measures::LevenshteinDamerau;
+    use crate::measures::LevenshteinDamerauOp::*;
+    use crate::op::{archetype, IndexedOperation, Operation};
+    use crate::{Align, CostMatrix, SeqPair};
+
+    use super::{Equal, Equivalence, EquivalenceFn, KeyEquivalence};
+
+    #[test]
+    fn case_insensitive_test() {
+        let tpyo: Vec<char> = "TpYo".chars().collect();
+        let typo: Vec<char> = "tYpo".chars().collect();
+
+        let measure = LevenshteinDamerau::new(1, 1, 1, 1);
+        assert_eq!(measure.distance(&tpyo, &typo), 2);
+
+        let case_insensitive = KeyEquivalence::new(|c: &char| c.to_ascii_lowercase());
+        let measure = measure.with_equivalence(case_insensitive.clone());
+        let alignment = measure.align(&tpyo, &typo);
+        assert_eq!(alignment.distance(), 1);
+        assert_eq!(
+            alignment.edit_script()[1],
+            IndexedOperation::new(Transpose(1, case_insensitive), 1, 1)
+        );
+    }
+
+    #[test]
+    fn float_tolerance_test() {
+        // f64 is not Eq, elements are matched with a tolerance.
+        let source = [0.0, 1.0, 2.0, 3.0];
+        let target = [0.05, 1.02, 3.01];
+
+        let measure = LevenshteinDamerau::new(1, 1, 1, 1)
+            .with_equivalence(EquivalenceFn::new(|a: &f64, b: &f64| (a - b).abs() < 0.1));
+        let alignment = measure.align(&source, &target);
+        assert_eq!(alignment.distance(), 1);
+        assert_eq!(
+            alignment.edit_script()[2],
+            IndexedOperation::new(Delete(1), 2, 2)
+        );
+    }
+
+    #[test]
+    fn unrestricted_transpose_test() {
+        let source = ['A', 'x', 'b'];
+        let target = ['B', 'a'];
+        let pair = SeqPair {
+            source: &source,
+            target: &target,
+        };
+        let cost_matrix = CostMatrix::new(4, 3);
+
+        let transpose = archetype::UnrestrictedTranspose {
+            transpose_cost: 1usize,
+            insert_cost: 2,
+            delete_cost: 3,
+        };
+        assert_eq!(Operation::<char>::backtrack(&transpose, &pair, 3, 2), None);
+
+        let transpose = archetype::UnrestrictedTransposeWith {
+            transpose_cost: 1usize,
+            insert_cost: 2,
+            delete_cost: 3,
+            equivalence: EquivalenceFn::new(|a: &char, b: &char| a.eq_ignore_ascii_case(b)),
+        };
+        assert_eq!(transpose.backtrack(&pair, 3, 2), Some((0, 0)));
+        assert_eq!(transpose.cost(&pair, &cost_matrix, 3, 2), Some(4));
+    }
+
+    #[test]
+    fn equivalence_test() {
+        assert!(Equal.equivalent(&'a', &'a'));
+        assert!(!Equal.equivalent(&'a', &'A'));
+
+        let tolerance = EquivalenceFn::new(|a: &f64, b: &f64| (a - b).abs() < 0.1);
+        assert!(tolerance.equivalent(&1.0, &1.05));
+        assert!(!tolerance.equivalent(&1.0, &1.2));
+        assert_eq!(tolerance, tolerance.clone());
+        assert_ne!(
+            tolerance,
+            EquivalenceFn::new(|a: &f64, b: &f64| (a - b).abs() < 0.1)
+        );
+
+        let by_len = KeyEquivalence::new(|word: &&str| word.len());
+        assert!(by_len.equivalent(&"foo", &"bar"));
+        assert!(!by_len.equivalent(&"foo", &"quux"));
+        assert_eq!(by_len, by_len.clone());
+    }
+}
@@ -0,0 +1,133 @@
+use std::error::Error;
+use std::fmt;
+
+/// Alignment errors.
+///
+/// These errors indicate that a measure is not well-defined for the
+/// sequences that are aligned, e.g. because one of the edit operations
+/// that is necessary to construct an alignment is missing.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum AlignError {
+    /// None of the measure's operations can be applied to compute the
+    /// cost of a cell of the cost matrix.
+    NoApplicableOperation {
+        source_idx: usize,
+        target_idx: usize,
+    },
+
+    /// There is no operation to backtrack from a cell of the cost matrix
+    /// towards cell (0, 0).
+    CannotBacktrack {
+        source_idx: usize,
+        target_idx: usize,
+    },
+
+    /// The measure can only align sequences of the same length.
+    LengthMismatch {
+        source_len: usize,
+        target_len: usize,
+    },
+
+    /// A symbol of one of the sequences is not in the substitution
+    /// matrix of the measure.
+    UnknownSymbol { symbol: char },
+
+    /// The window of the measure does not contain a path from cell
+    /// (0, 0) to the last cell of the cost matrix.
+    EmptyWindow {
+        source_len: usize,
+        target_len: usize,
+    },
+
+    /// An operation of the measure consumes more source elements than
+    /// the alignment algorithm supports.
+    UnsupportedLookback { lookback: usize },
+}
+
+impl AlignError {
+    /// Map the cost matrix cell of the error.
+    ///
+    /// This is used when an error occurs while aligning subsequences, to
+    /// report the cell of the cost matrix of the full sequences.
+    pub(crate) fn map_cell<F>(self, f: F) -> Self
+    where
+        F: FnOnce(usize, usize) -> (usize, usize),
+    {
+        use self::AlignError::*;
+
+        match self {
+            NoApplicableOperation {
+                source_idx,
+                target_idx,
+            } => {
+                let (source_idx, target_idx) = f(source_idx, target_idx);
+                NoApplicableOperation {
+                    source_idx,
+                    target_idx,
+                }
+            }
+            CannotBacktrack {
+                source_idx,
+                target_idx,
+            } => {
+                let (source_idx, target_idx) = f(source_idx, target_idx);
+                CannotBacktrack {
+                    source_idx,
+                    target_idx,
+                }
+            }
+            err => err,
+        }
+    }
+}
+
+impl fmt::Display for AlignError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::AlignError::*;
+
+        match *self {
+            NoApplicableOperation {
+                source_idx,
+                target_idx,
+            } => write!(
+                f,
+                "No applicable operation for cell ({}, {})",
+                source_idx, target_idx
+            ),
+            CannotBacktrack {
+                source_idx,
+                target_idx,
+            } => write!(
+                f,
+                "Cannot backtrack from cell ({}, {}) to cell (0, 0)",
+                source_idx, target_idx
+            ),
+            LengthMismatch {
+                source_len,
+                target_len,
+            } => write!(
+                f,
+                "Cannot align sequences of different lengths ({} and {})",
+                source_len, target_len
+            ),
+            UnknownSymbol { symbol } => {
+                write!(f, "Symbol '{}' is not in the substitution matrix", symbol)
+            }
+            EmptyWindow {
+                source_len,
+                target_len,
+            } => write!(
+                f,
+                "Window does not contain a path for sequences of lengths {} and {}",
+                source_len, target_len
+            ),
+            UnsupportedLookback { lookback } => write!(
+                f,
+                "Hirschberg alignment requires operations that consume at most one source element, found an operation that consumes {}",
+                lookback
+            ),
+        }
+    }
+}
+
+impl Error for AlignError {}
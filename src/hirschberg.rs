@@ -0,0 +1,313 @@
+use crate::cost::Cost;
+use crate::dynprog::{last_row, max_lookback, Align};
+use crate::error::AlignError;
+use crate::op::IndexedOperation;
+use crate::{Measure, SeqPair};
+
+/// Trait enabling linear-space alignment of `Measure`s.
+///
+/// This trait implements Hirschberg's divide-and-conquer alignment for
+/// every type that implements the `Measure` trait. Rather than storing
+/// the full cost matrix, the sequences are recursively split at the cell
+/// where an optimal alignment crosses the middle row of the cost matrix.
+/// This only requires memory that is linear in the sequence lengths, at
+/// the cost of roughly doubling the computation time.
+///
+/// Hirschberg alignment requires that every operation of the measure
+/// consumes at most one element of the source sequence and that the
+/// operation costs do not depend on the direction of the alignment. This
+/// is the case for measures that are built from the `Insert`, `Delete`,
+/// `Match`, and `Substitute` archetypes.
+pub trait HirschbergAlign<M, S, U = S>
+where
+    M: Measure<S, U>,
+    S: Clone,
+    U: Clone,
+{
+    /// Return the script of edit operations to rewrite the source sequence
+    /// to the target sequence.
+    ///
+    /// If there are multiple possible edit scripts, this method will return
+    /// one of the possible edit scripts, which is not necessarily the same
+    /// script as the one returned by `Alignment::edit_script`.
+    ///
+    /// Panics when one of the measure's operations consumes more than one
+    /// source element or when none of the measure's operations can be
+    /// applied to a cell of the cost matrix. Use
+    /// `try_hirschberg_edit_script` to get an error instead.
+    fn hirschberg_edit_script(
+        &self,
+        source: &[S],
+        target: &[U],
+    ) -> Vec<IndexedOperation<M::Operation>>;
+
+    /// Return the script of edit operations to rewrite the source sequence
+    /// to the target sequence.
+    ///
+    /// This method is the same as `hirschberg_edit_script`, but returns an
+    /// error when one of the measure's operations consumes more than one
+    /// source element, when none of the measure's operations can be
+    /// applied to a cell of the cost matrix, or when it is not possible to
+    /// backtrack.
+    fn try_hirschberg_edit_script(
+        &self,
+        source: &[S],
+        target: &[U],
+    ) -> Result<Vec<IndexedOperation<M::Operation>>, AlignError>;
+}
+
+impl<M, S, U> HirschbergAlign<M, S, U> for M
+where
+    M: Measure<S, U>,
+    S: Clone,
+    U: Clone,
+{
+    fn hirschberg_edit_script(
+        &self,
+        source: &[S],
+        target: &[U],
+    ) -> Vec<IndexedOperation<M::Operation>> {
+        self.try_hirschberg_edit_script(source, target)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_hirschberg_edit_script(
+        &self,
+        source: &[S],
+        target: &[U],
+    ) -> Result<Vec<IndexedOperation<M::Operation>>, AlignError> {
+        let (lookback, _) = max_lookback(self, &SeqPair { source, target });
+        if lookback > 1 {
+            return Err(AlignError::UnsupportedLookback { lookback });
+        }
+
+        let rev_source: Vec<S> = source.iter().rev().cloned().collect();
+        let rev_target: Vec<U> = target.iter().rev().cloned().collect();
+
+        let hirschberg = Hirschberg {
+            measure: self,
+            source,
+            target,
+            rev_source: &rev_source,
+            rev_target: &rev_target,
+        };
+
+        let mut script = Vec::new();
+        hirschberg.edit_script(0, source.len(), 0, target.len(), &mut script)?;
+
+        Ok(script)
+    }
+}
+
+struct Hirschberg<'a, M, S, U> {
+    measure: &'a M,
+    source: &'a [S],
+    target: &'a [U],
+    rev_source: &'a [S],
+    rev_target: &'a [U],
+}
+
+impl<'a, M, S, U> Hirschberg<'a, M, S, U>
+where
+    M: Measure<S, U>,
+{
+    /// Add the edit script for `source[source_start..source_end]` and
+    /// `target[target_start..target_end]` to `script`.
+    fn edit_script(
+        &self,
+        source_start: usize,
+        source_end: usize,
+        target_start: usize,
+        target_end: usize,
+        script: &mut Vec<IndexedOperation<M::Operation>>,
+    ) -> Result<(), AlignError> {
+        let source = &self.source[source_start..source_end];
+        let target = &self.target[target_start..target_end];
+
+        // Base case: the cost matrix has at most two rows, so we can
+        // align using the full cost matrix.
+        if source.len() <= 1 {
+            let edit_script = self
+                .measure
+                .try_align(source, target)
+                .and_then(|alignment| alignment.try_edit_script())
+                .map_err(|err| {
+                    err.map_cell(|source_idx, target_idx| {
+                        (source_start + source_idx, target_start + target_idx)
+                    })
+                })?;
+
+            for op in edit_script {
+                script.push(IndexedOperation::new(
+                    op.operation().clone(),
+                    source_start + op.source_idx(),
+                    target_start + op.target_idx(),
+                ));
+            }
+
+            return Ok(());
+        }
+
+        let source_mid = source_start + source.len() / 2;
+
+        // Costs of aligning the first half of the source with every
+        // prefix of the target.
+        let forward = last_row(
+            self.measure,
+            &SeqPair {
+                source: &self.source[source_start..source_mid],
+                target,
+            },
+        )
+        .map_err(|err| {
+            err.map_cell(|source_idx, target_idx| {
+                (source_start + source_idx, target_start + target_idx)
+            })
+        })?;
+
+        // Costs of aligning the second half of the source with every
+        // suffix of the target.
+        let n_rev_source = self.rev_source.len();
+        let n_rev_target = self.rev_target.len();
+        let backward = last_row(
+            self.measure,
+            &SeqPair {
+                source: &self.rev_source[n_rev_source - source_end..n_rev_source - source_mid],
+                target: &self.rev_target[n_rev_target - target_end..n_rev_target - target_start],
+            },
+        )
+        .map_err(|err| {
+            err.map_cell(|source_idx, target_idx| {
+                (source_end - source_idx, target_end - target_idx)
+            })
+        })?;
+
+        // Find the column where an optimal alignment crosses the middle row.
+        let split = (0..=target.len())
+            .min_by_key(|&idx| forward[idx].saturating_add(backward[target.len() - idx]))
+            .expect("Cost matrix without columns");
+        let target_mid = target_start + split;
+
+        self.edit_script(source_start, source_mid, target_start, target_mid, script)?;
+        self.edit_script(source_mid, source_end, target_mid, target_end, script)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::measures::{Levenshtein, LevenshteinDamerau, LCS};
+    use crate::op::archetype;
+    use crate::{Align, AlignError, Measure};
+
+    use super::HirschbergAlign;
+
+    /// Measure that can only match elements.
+    struct MatchOnly([archetype::Match; 1]);
+
+    impl Measure<char> for MatchOnly {
+        type Cost = usize;
+
+        type Operation = archetype::Match;
+
+        fn operations(&self) -> &[Self::Operation] {
+            &self.0
+        }
+    }
+
+    static PAIRS: &[(&str, &str)] = &[
+        ("pineapple", ""),
+        ("", "pineapple"),
+        ("pineapple", "pen"),
+        ("pen", "pineapple"),
+        ("pineapple", "applet"),
+        ("applet", "aplpet"),
+        ("tpyo", "typo"),
+        (
+            "the quick brown fox jumps over the lazy dog",
+            "a quick brown dog jumped over the lazy fox",
+        ),
+    ];
+
+    #[test]
+    fn hirschberg_levenshtein_test() {
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        for &(source, target) in PAIRS {
+            let source: Vec<char> = source.chars().collect();
+            let target: Vec<char> = target.chars().collect();
+
+            let script = levenshtein.hirschberg_edit_script(&source, &target);
+            assert!(levenshtein
+                .align(&source, &target)
+                .edit_scripts()
+                .contains(&script));
+        }
+    }
+
+    #[test]
+    fn hirschberg_empty_test() {
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let empty: &[char] = &[];
+        assert!(levenshtein.hirschberg_edit_script(empty, empty).is_empty());
+    }
+
+    #[test]
+    fn hirschberg_lcs_test() {
+        let lcs = LCS::new(1, 1);
+
+        for &(source, target) in PAIRS {
+            let source: Vec<char> = source.chars().collect();
+            let target: Vec<char> = target.chars().collect();
+
+            let script = lcs.hirschberg_edit_script(&source, &target);
+            assert!(lcs.align(&source, &target).edit_scripts().contains(&script));
+        }
+    }
+
+    #[test]
+    fn try_hirschberg_test() {
+        let match_only = MatchOnly([archetype::Match]);
+        let source: Vec<char> = "pine".chars().collect();
+        let target: Vec<char> = "pane".chars().collect();
+
+        assert_eq!(
+            match_only.try_hirschberg_edit_script(&source, &target),
+            Err(AlignError::NoApplicableOperation {
+                source_idx: 0,
+                target_idx: 1
+            })
+        );
+    }
+
+    #[test]
+    fn try_hirschberg_transpose_test() {
+        let levenshtein_damerau = LevenshteinDamerau::new(1, 1, 1, 1);
+        let source: Vec<char> = "tpyo".chars().collect();
+        let target: Vec<char> = "typo".chars().collect();
+
+        assert_eq!(
+            levenshtein_damerau.try_hirschberg_edit_script(&source, &target),
+            Err(AlignError::UnsupportedLookback { lookback: 2 })
+        );
+
+        // Scripts without transpositions can still be found when the
+        // source is too short to transpose elements.
+        assert_eq!(
+            levenshtein_damerau.try_hirschberg_edit_script(&source[..1], &target),
+            Ok(levenshtein_damerau
+                .align(&source[..1], &target)
+                .edit_script())
+        );
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Hirschberg alignment requires operations that consume at most one source element"
+    )]
+    fn hirschberg_transpose_test() {
+        let levenshtein_damerau = LevenshteinDamerau::new(1, 1, 1, 1);
+        let source: Vec<char> = "tpyo".chars().collect();
+        let target: Vec<char> = "typo".chars().collect();
+        levenshtein_damerau.hirschberg_edit_script(&source, &target);
+    }
+}
@@ -5,8 +5,10 @@
 //! distances. However, to my knowledge there was no crate that supports
 //! all of the following seqalign features:
 //!
-//! * Works on slices of any type.
+//! * Works on slices of any type, the source and target can have
+//!   different element types.
 //! * Can return both the edit distance and the edit script/alignment.
+//! * Supports integer, saturating, and floating point costs.
 //! * Can be extended with new measures.
 //!
 //! # Example
@@ -25,13 +27,14 @@
 //! assert_eq!(2, alignment.distance());
 //!
 //! // Get the edit script.
+//! use seqalign::equivalence::Equal;
 //! use seqalign::measures::LevenshteinDamerauOp;
 //! use seqalign::op::IndexedOperation;
 //!
 //! assert_eq!(vec![
-//!     IndexedOperation::new(LevenshteinDamerauOp::Match, 0, 0),
-//!     IndexedOperation::new(LevenshteinDamerauOp::Transpose(1), 1, 1),
-//!     IndexedOperation::new(LevenshteinDamerauOp::Match, 3, 3),
+//!     IndexedOperation::new(LevenshteinDamerauOp::Match(Equal), 0, 0),
+//!     IndexedOperation::new(LevenshteinDamerauOp::Transpose(1, Equal), 1, 1),
+//!     IndexedOperation::new(LevenshteinDamerauOp::Match(Equal), 3, 3),
 //!     IndexedOperation::new(LevenshteinDamerauOp::Insert(1), 4, 4)
 //!   ], alignment.edit_script());
 //! ```
@@ -48,25 +51,105 @@ extern crate maplit;
 #[macro_use]
 extern crate pretty_assertions;
 
+mod affine;
+pub use crate::affine::AffineAlignment;
+
+mod aligner;
+pub use crate::aligner::{Aligner, AlignmentResult, PrefixAligner};
+
+pub mod cost;
+
+mod dtw;
+pub use crate::dtw::DtwAlignment;
+
 mod dynprog;
-pub use crate::dynprog::{Align, Alignment};
+pub use crate::dynprog::{Align, Alignment, EditScriptsIter};
+
+pub mod equivalence;
+
+mod error;
+pub use crate::error::AlignError;
+
+mod hirschberg;
+pub use crate::hirschberg::HirschbergAlign;
+
+mod local;
+pub use crate::local::{LocalAlign, LocalAlignment};
+
+mod matrix;
+pub use crate::matrix::CostMatrix;
 
 pub mod measures;
 
+mod mode;
+pub use crate::mode::AlignmentMode;
+
+mod msa;
+pub use crate::msa::{GuideTree, MultipleAlign, MultipleAlignment};
+
+mod myers;
+
+mod normalize;
+pub use crate::normalize::Normalization;
+
 pub mod op;
 
+#[cfg(feature = "rayon")]
+pub mod parallel;
+
+mod substitution;
+pub use crate::substitution::{SubstitutionMatrix, SubstitutionMatrixError};
+
 /// Trait for edit distance measures.
-pub trait Measure<T> {
+///
+/// The type parameters `S` and `U` are the element types of the source
+/// and target sequences. Both sequences have the same element type by
+/// default.
+pub trait Measure<S, U = S> {
+    /// The type of the costs of the measure's edit operations.
+    type Cost: cost::Cost;
+
     /// The edit operations associated with the measure.
-    type Operation: op::Operation<T>;
+    type Operation: op::Operation<S, Self::Cost, U>;
 
     /// Get a slice with the measure's operations. Typically, this contains
     /// all the enum variants of the associated type `Operation`.
     fn operations(&self) -> &[Self::Operation];
+
+    /// Compute the edit distance using a measure-specific algorithm.
+    ///
+    /// This method is used by `Align::distance` when a measure has a
+    /// faster way to compute the edit distance than filling the cost
+    /// matrix. It should return `None` when there is no such algorithm
+    /// for the given sequences. The default implementation always
+    /// returns `None`.
+    fn fast_distance(&self, _source: &[S], _target: &[U]) -> Option<Self::Cost> {
+        None
+    }
+
+    /// Check whether the measure is symmetric.
+    ///
+    /// A measure is symmetric when the distance from *a* to *b* is
+    /// always equal to the distance from *b* to *a*. This is used to
+    /// avoid computing distances twice. The default implementation
+    /// returns `false`.
+    fn is_symmetric(&self) -> bool {
+        false
+    }
+
+    /// Check that the measure can align the given sequences.
+    ///
+    /// This method is called before the cost matrix is filled, so that
+    /// a measure can reject elements that its operations cannot be
+    /// applied to, e.g. symbols that are not in a substitution matrix.
+    /// The default implementation accepts all sequences.
+    fn check_sequences(&self, _source: &[S], _target: &[U]) -> Result<(), AlignError> {
+        Ok(())
+    }
 }
 
 /// A pairing of two sequences.
-pub struct SeqPair<'a, T> {
-    pub source: &'a [T],
-    pub target: &'a [T],
+pub struct SeqPair<'a, S, U = S> {
+    pub source: &'a [S],
+    pub target: &'a [U],
 }
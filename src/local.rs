@@ -0,0 +1,304 @@
+use std::ops::Range;
+
+use crate::cost::Cost;
+use crate::error::AlignError;
+use crate::op::Operation;
+use crate::{Align, Alignment, CostMatrix, Measure, SeqPair};
+
+/// Trait enabling local alignment of `Measure`s.
+///
+/// Local alignment (Smith-Waterman) finds the pair of subsequences of the
+/// source and target sequence that align best. Since the empty
+/// subsequences can always be aligned without any cost, local alignment
+/// maximizes a score rather than minimizing a cost. The measure's costs
+/// are mapped to scores by rewarding every sequence element that an
+/// operation consumes with `element_score`. The score of an operation is
+/// the reward minus the cost of the operation. For example, with
+/// `Levenshtein::new(2, 2, 3)` and an element score of *1*:
+///
+/// * a match consumes two elements and scores *2*;
+/// * a substitution consumes two elements and scores *2 - 3 = -1*;
+/// * an insertion or deletion consumes one element and scores *1 - 2 =
+///   -1*.
+///
+/// An alignment can restart at every cell of the score matrix, so that
+/// scores never become negative. The cost of an operation is found by
+/// applying the operation to a cost matrix of zeros, so the cost of an
+/// operation must be the cost of the cell that it backtracks to plus the
+/// operation's own cost.
+pub trait LocalAlign<'a, M, S, U = S>
+where
+    M: Measure<S, U>,
+{
+    /// Align the best-matching subsequences of two sequences.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of the cost matrix of the subsequences. Use `try_local_align`
+    /// to get an error instead.
+    fn local_align(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        element_score: M::Cost,
+    ) -> LocalAlignment<'a, M, S, U>;
+
+    /// Align the best-matching subsequences of two sequences.
+    ///
+    /// An error is returned when none of the measure's operations can be
+    /// applied to a cell of the cost matrix of the subsequences, or when
+    /// the best-scoring cell cannot be traced back to the start of the
+    /// local alignment.
+    fn try_local_align(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        element_score: M::Cost,
+    ) -> Result<LocalAlignment<'a, M, S, U>, AlignError>;
+}
+
+impl<'a, M, S, U> LocalAlign<'a, M, S, U> for M
+where
+    M: Measure<S, U>,
+{
+    fn local_align(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        element_score: M::Cost,
+    ) -> LocalAlignment<'a, M, S, U> {
+        self.try_local_align(source, target, element_score)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    fn try_local_align(
+        &'a self,
+        source: &'a [S],
+        target: &'a [U],
+        element_score: M::Cost,
+    ) -> Result<LocalAlignment<'a, M, S, U>, AlignError> {
+        self.check_sequences(source, target)?;
+
+        let pair = SeqPair { source, target };
+        let scorer: Scorer<M, S, U> = Scorer {
+            pair: &pair,
+            element_score,
+            zeros: CostMatrix::with_stored_rows(source.len() + 1, target.len() + 1, 1),
+        };
+
+        // Fill the score matrix, remembering the cell with the highest
+        // score. Every cell starts with a score of zero, which is the
+        // score of restarting the alignment.
+        let mut scores = CostMatrix::new(source.len() + 1, target.len() + 1);
+        let mut best = (0, 0);
+        for source_idx in 0..=source.len() {
+            for target_idx in 0..=target.len() {
+                let score = self
+                    .operations()
+                    .iter()
+                    .filter_map(|op| scorer.score(op, &scores, source_idx, target_idx))
+                    .fold(M::Cost::zero(), Ord::max);
+                scores[(source_idx, target_idx)] = score;
+
+                if score > scores[best] {
+                    best = (source_idx, target_idx);
+                }
+            }
+        }
+
+        // Trace back from the best cell until the score is zero.
+        let (mut source_idx, mut target_idx) = best;
+        while scores[(source_idx, target_idx)] > M::Cost::zero() {
+            let (from_source_idx, from_target_idx) = self
+                .operations()
+                .iter()
+                .filter(|op| {
+                    scorer.score(op, &scores, source_idx, target_idx)
+                        == Some(scores[(source_idx, target_idx)])
+                })
+                .filter_map(|op| op.backtrack(&pair, source_idx, target_idx))
+                .find(|&from| from != (source_idx, target_idx))
+                .ok_or(AlignError::CannotBacktrack {
+                    source_idx,
+                    target_idx,
+                })?;
+
+            source_idx = from_source_idx;
+            target_idx = from_target_idx;
+        }
+
+        let source_span = source_idx..best.0;
+        let target_span = target_idx..best.1;
+
+        Ok(LocalAlignment {
+            alignment: self
+                .try_align(&source[source_span.clone()], &target[target_span.clone()])?,
+            score: scores[best],
+            source_span,
+            target_span,
+        })
+    }
+}
+
+/// Scoring of operations for local alignment.
+struct Scorer<'a, 'b, M, S, U>
+where
+    M: Measure<S, U>,
+{
+    pair: &'a SeqPair<'b, S, U>,
+    element_score: M::Cost,
+    zeros: CostMatrix<M::Cost>,
+}
+
+impl<'a, 'b, M, S, U> Scorer<'a, 'b, M, S, U>
+where
+    M: Measure<S, U>,
+{
+    /// Compute the score of a cell when it is reached using `op`.
+    ///
+    /// Returns `None` if the operation cannot be applied.
+    fn score(
+        &self,
+        op: &M::Operation,
+        scores: &CostMatrix<M::Cost>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<M::Cost> {
+        let (from_source_idx, from_target_idx) = op.backtrack(self.pair, source_idx, target_idx)?;
+        let op_cost = op.cost(self.pair, &self.zeros, source_idx, target_idx)?;
+
+        let n_elements = (source_idx - from_source_idx) + (target_idx - from_target_idx);
+        let reward = (0..n_elements).fold(M::Cost::zero(), |reward, _| {
+            reward.saturating_add(self.element_score)
+        });
+
+        Some(
+            scores[(from_source_idx, from_target_idx)]
+                .saturating_add(reward)
+                .saturating_sub(op_cost),
+        )
+    }
+}
+
+/// Local alignment of two sequences.
+///
+/// A local alignment consists of the spans of the best-matching
+/// subsequences and their alignment. The alignment is the (global)
+/// alignment of the subsequences, so the indices in its edit scripts are
+/// relative to the start of the spans.
+pub struct LocalAlignment<'a, M, S, U = S>
+where
+    M: Measure<S, U>,
+{
+    alignment: Alignment<'a, M, S, U>,
+    score: M::Cost,
+    source_span: Range<usize>,
+    target_span: Range<usize>,
+}
+
+impl<'a, M, S, U> LocalAlignment<'a, M, S, U>
+where
+    M: Measure<S, U>,
+{
+    /// Get the alignment of the subsequences.
+    pub fn alignment(&self) -> &Alignment<'a, M, S, U> {
+        &self.alignment
+    }
+
+    /// Get the alignment of the subsequences, consuming the local
+    /// alignment.
+    pub fn into_alignment(self) -> Alignment<'a, M, S, U> {
+        self.alignment
+    }
+
+    /// Get the score of the local alignment.
+    pub fn score(&self) -> M::Cost {
+        self.score
+    }
+
+    /// Get the span of the aligned source subsequence.
+    pub fn source_span(&self) -> Range<usize> {
+        self.source_span.clone()
+    }
+
+    /// Get the span of the aligned target subsequence.
+    pub fn target_span(&self) -> Range<usize> {
+        self.target_span.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equivalence::Equal;
+    use crate::measures::LevenshteinOp::*;
+    use crate::measures::{Levenshtein, LevenshteinDamerau};
+    use crate::op::IndexedOperation;
+
+    use super::LocalAlign;
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn local_align_test() {
+        let levenshtein = Levenshtein::new(2, 2, 3);
+
+        let pineapple = chars("pineapple");
+        let applet = chars("applet");
+        let local = levenshtein.local_align(&pineapple, &applet, 1);
+        assert_eq!(local.score(), 10);
+        assert_eq!(local.source_span(), 4..9);
+        assert_eq!(local.target_span(), 0..5);
+        assert_eq!(local.alignment().distance(), 0);
+
+        let source = chars("xxabcdyy");
+        let target = chars("zabxcdz");
+        let local = levenshtein.local_align(&source, &target, 1);
+        assert_eq!(local.score(), 7);
+        assert_eq!(local.source_span(), 2..6);
+        assert_eq!(local.target_span(), 1..6);
+        assert_eq!(
+            local.alignment().edit_script(),
+            vec![
+                IndexedOperation::new(Match(Equal), 0, 0),
+                IndexedOperation::new(Match(Equal), 1, 1),
+                IndexedOperation::new(Insert(2), 2, 2),
+                IndexedOperation::new(Match(Equal), 2, 3),
+                IndexedOperation::new(Match(Equal), 3, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn local_align_transpose_test() {
+        let source = chars("xxtpyoxx");
+        let target = chars("zztypoz");
+
+        let levenshtein = Levenshtein::new(2, 2, 3);
+        let local = levenshtein.local_align(&source, &target, 1);
+        assert_eq!(local.score(), 4);
+
+        let damerau = LevenshteinDamerau::new(2, 2, 3, 2);
+        let local = damerau.local_align(&source, &target, 1);
+        assert_eq!(local.score(), 6);
+        assert_eq!(local.source_span(), 2..6);
+        assert_eq!(local.target_span(), 2..6);
+        assert_eq!(local.alignment().distance(), 2);
+    }
+
+    #[test]
+    fn local_align_no_match_test() {
+        let levenshtein = Levenshtein::new(2, 2, 3);
+
+        let abc = chars("abc");
+        let xyz = chars("xyz");
+        let local = levenshtein.local_align(&abc, &xyz, 1);
+        assert_eq!(local.score(), 0);
+        assert_eq!(local.source_span(), 0..0);
+        assert_eq!(local.target_span(), 0..0);
+        assert!(local.alignment().edit_script().is_empty());
+
+        let local = levenshtein.local_align(&abc, &[], 1);
+        assert_eq!(local.score(), 0);
+    }
+}
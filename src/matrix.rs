@@ -0,0 +1,327 @@
+use std::hash::{Hash, Hasher};
+use std::ops::{Index, IndexMut};
+
+use crate::cost::Cost;
+
+/// Edit distance cost matrix.
+///
+/// The cost matrix stores the cost of aligning every prefix of the source
+/// sequence (rows) with every prefix of the target sequence (columns). The
+/// costs are stored contiguously in row-major order.
+#[derive(Clone, Debug)]
+pub struct CostMatrix<C = usize> {
+    data: Vec<C>,
+    rows: usize,
+    columns: usize,
+    stored_rows: usize,
+    stride: usize,
+}
+
+impl<C> CostMatrix<C>
+where
+    C: Cost,
+{
+    /// Construct a cost matrix with the given shape, where every cell has
+    /// cost zero.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self::from_elem(C::zero(), rows, columns)
+    }
+
+    /// Construct a cost matrix with the given shape, where every cell has
+    /// cost `cost`.
+    pub fn from_elem(cost: C, rows: usize, columns: usize) -> Self {
+        CostMatrix {
+            data: vec![cost; rows * columns],
+            rows,
+            columns,
+            stored_rows: rows,
+            stride: columns,
+        }
+    }
+
+    /// Construct a cost matrix that only stores the last `stored_rows`
+    /// rows.
+    ///
+    /// The number of stored rows is rounded up to a power of two. Row *i*
+    /// is stored in the same memory as row *i - stored_rows*, so only the
+    /// last `stored_rows` rows that were written can be read back.
+    pub(crate) fn with_stored_rows(rows: usize, columns: usize, stored_rows: usize) -> Self {
+        let stored_rows = stored_rows.max(1).next_power_of_two();
+        if stored_rows >= rows {
+            return Self::new(rows, columns);
+        }
+
+        CostMatrix {
+            data: vec![C::zero(); stored_rows * columns],
+            rows,
+            columns,
+            stored_rows,
+            stride: columns,
+        }
+    }
+
+    /// Change the shape of the cost matrix and set every cell to zero.
+    ///
+    /// The storage of the matrix is reused, so this only allocates when
+    /// the matrix grows beyond its largest size so far.
+    pub(crate) fn reset(&mut self, rows: usize, columns: usize) {
+        self.data.clear();
+        self.data.resize(rows * columns, C::zero());
+        self.rows = rows;
+        self.columns = columns;
+        self.stored_rows = rows;
+        self.stride = columns;
+    }
+
+    /// Change the number of columns, preserving the costs of the columns
+    /// that are retained. Cells in new columns have cost zero.
+    ///
+    /// Rows are padded to leave room for additional columns, so that
+    /// repeatedly adding and removing columns does not move the existing
+    /// costs around in memory every time.
+    pub(crate) fn resize_columns(&mut self, columns: usize) {
+        if columns > self.stride {
+            let stride = columns.max(2 * self.stride);
+            let mut data = vec![C::zero(); self.stored_rows * stride];
+            for row in 0..self.stored_rows {
+                let old_offset = row * self.stride;
+                data[row * stride..row * stride + self.columns]
+                    .copy_from_slice(&self.data[old_offset..old_offset + self.columns]);
+            }
+
+            self.data = data;
+            self.stride = stride;
+        } else if columns > self.columns {
+            for row in 0..self.stored_rows {
+                let offset = row * self.stride;
+                for cost in &mut self.data[offset + self.columns..offset + columns] {
+                    *cost = C::zero();
+                }
+            }
+        }
+
+        self.columns = columns;
+    }
+
+    /// Get the number of columns.
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Get the costs of a column.
+    ///
+    /// Panics when the matrix does not store all rows, since the
+    /// column would then contain costs of other rows.
+    pub fn column(&self, column: usize) -> impl Iterator<Item = C> + '_ {
+        assert!(
+            self.stored_rows == self.rows,
+            "Cannot get column of matrix that stores {} of {} rows",
+            self.stored_rows,
+            self.rows
+        );
+        assert!(
+            column < self.columns,
+            "Column {} out of bounds for matrix with {} columns",
+            column,
+            self.columns
+        );
+
+        (0..self.rows).map(move |row| self[(row, column)])
+    }
+
+    /// Get the costs of a row.
+    pub fn row(&self, row: usize) -> &[C] {
+        let offset = self.row_offset(row);
+        &self.data[offset..offset + self.columns]
+    }
+
+    /// Get the costs of a row mutably.
+    pub fn row_mut(&mut self, row: usize) -> &mut [C] {
+        let offset = self.row_offset(row);
+        &mut self.data[offset..offset + self.columns]
+    }
+
+    /// Get the number of rows.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Get the shape of the matrix as *(rows, columns)*.
+    pub fn shape(&self) -> (usize, usize) {
+        (self.rows, self.columns)
+    }
+
+    #[inline]
+    fn cell_offset(&self, row: usize, column: usize) -> usize {
+        assert!(
+            column < self.columns,
+            "Column {} out of bounds for matrix with {} columns",
+            column,
+            self.columns
+        );
+
+        self.row_offset(row) + column
+    }
+
+    #[inline]
+    fn row_offset(&self, row: usize) -> usize {
+        assert!(
+            row < self.rows,
+            "Row {} out of bounds for matrix with {} rows",
+            row,
+            self.rows
+        );
+
+        if self.stored_rows == self.rows {
+            row * self.stride
+        } else {
+            (row & (self.stored_rows - 1)) * self.stride
+        }
+    }
+
+    /// Get the rows that are stored.
+    fn stored_rows(&self) -> impl Iterator<Item = &[C]> {
+        (0..self.stored_rows.min(self.rows)).map(move |row| {
+            let offset = row * self.stride;
+            &self.data[offset..offset + self.columns]
+        })
+    }
+}
+
+impl<C> Eq for CostMatrix<C> where C: Cost {}
+
+impl<C> Hash for CostMatrix<C>
+where
+    C: Cost,
+{
+    fn hash<H>(&self, state: &mut H)
+    where
+        H: Hasher,
+    {
+        self.rows.hash(state);
+        self.columns.hash(state);
+        self.stored_rows.hash(state);
+        for row in self.stored_rows() {
+            row.hash(state);
+        }
+    }
+}
+
+// Rows can be padded (see `resize_columns`), so padding is excluded
+// when comparing or hashing matrices.
+impl<C> PartialEq for CostMatrix<C>
+where
+    C: Cost,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.rows == other.rows
+            && self.columns == other.columns
+            && self.stored_rows == other.stored_rows
+            && self.stored_rows().eq(other.stored_rows())
+    }
+}
+
+impl<C> Index<(usize, usize)> for CostMatrix<C>
+where
+    C: Cost,
+{
+    type Output = C;
+
+    #[inline]
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        &self.data[self.cell_offset(row, column)]
+    }
+}
+
+impl<C> IndexMut<(usize, usize)> for CostMatrix<C>
+where
+    C: Cost,
+{
+    #[inline]
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+        let offset = self.cell_offset(row, column);
+        &mut self.data[offset]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CostMatrix;
+
+    #[test]
+    fn index_test() {
+        let mut matrix: CostMatrix = CostMatrix::new(3, 2);
+        assert_eq!(matrix.shape(), (3, 2));
+
+        matrix[(0, 1)] = 1;
+        matrix[(2, 0)] = 2;
+        matrix.row_mut(1)[1] = 3;
+
+        assert_eq!(matrix[(0, 1)], 1);
+        assert_eq!(matrix[(2, 0)], 2);
+        assert_eq!(matrix.row(0), &[0, 1]);
+        assert_eq!(matrix.row(1), &[0, 3]);
+        assert_eq!(matrix.row(2), &[2, 0]);
+        assert_eq!(matrix.column(0).collect::<Vec<_>>(), vec![0, 0, 2]);
+        assert_eq!(matrix.column(1).collect::<Vec<_>>(), vec![1, 3, 0]);
+    }
+
+    #[test]
+    fn stored_rows_test() {
+        let mut matrix = CostMatrix::with_stored_rows(4, 2, 2);
+        for row in 0..4 {
+            matrix.row_mut(row).copy_from_slice(&[row, row + 1]);
+            assert_eq!(matrix.row(row), &[row, row + 1]);
+        }
+
+        assert_eq!(matrix.row(2), &[2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot get column of matrix that stores 2 of 4 rows")]
+    fn stored_rows_column_test() {
+        let matrix: CostMatrix = CostMatrix::with_stored_rows(4, 2, 2);
+        matrix.column(0).count();
+    }
+
+    #[test]
+    fn reset_test() {
+        let mut matrix: CostMatrix = CostMatrix::from_elem(1, 3, 2);
+        matrix.reset(2, 2);
+        assert_eq!(matrix.shape(), (2, 2));
+        assert_eq!(matrix.row(0), &[0, 0]);
+        assert_eq!(matrix.row(1), &[0, 0]);
+    }
+
+    #[test]
+    fn resize_columns_test() {
+        let mut matrix: CostMatrix = CostMatrix::new(2, 2);
+        matrix.row_mut(0).copy_from_slice(&[1, 2]);
+        matrix.row_mut(1).copy_from_slice(&[3, 4]);
+
+        matrix.resize_columns(3);
+        assert_eq!(matrix.shape(), (2, 3));
+        assert_eq!(matrix.row(0), &[1, 2, 0]);
+        assert_eq!(matrix.row(1), &[3, 4, 0]);
+
+        matrix[(1, 2)] = 5;
+        matrix.resize_columns(1);
+        assert_eq!(matrix.row(0), &[1]);
+        assert_eq!(matrix.row(1), &[3]);
+
+        matrix.resize_columns(2);
+        assert_eq!(matrix.row(1), &[3, 0]);
+
+        let mut expected: CostMatrix = CostMatrix::new(2, 2);
+        expected[(0, 0)] = 1;
+        expected[(1, 0)] = 3;
+        assert_eq!(matrix, expected);
+    }
+
+    #[test]
+    #[should_panic]
+    fn out_of_bounds_test() {
+        let matrix: CostMatrix = CostMatrix::new(3, 2);
+        let _ = matrix[(3, 0)];
+    }
+}
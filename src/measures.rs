@@ -2,18 +2,31 @@
 //!
 //! This module provides some predefined sequence distance measures.
 
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::cost::{Cost, ElementCosts};
+use crate::equivalence::{Equal, Equivalence};
+use crate::myers;
 use crate::op::archetype;
-use crate::op::Operation;
-use crate::{Measure, SeqPair};
+use crate::op::{EditScript, IndexedOperation, Operation};
+use crate::{AlignError, CostMatrix, Measure, SeqPair, SubstitutionMatrix};
+
+pub use crate::affine::{AffineGap, AffineGapOp};
+pub use crate::dtw::{Dtw, DtwOp, StepPattern, Window};
 
 macro_rules! op_mapping {
     ( $op_type:ident, $mapping:tt ) => {
-        impl<T> Operation<T> for $op_type
+        impl<T, C, E> Operation<T, C> for $op_type<C, E>
         where
-            T: Eq,
+            C: Cost,
+            E: Clone + Debug + Equivalence<T> + Eq + Hash,
         {
             cost_fun!($op_type, $mapping);
             backtrack_fun!($op_type, $mapping);
+            unbounded_lookback_fun!($op_type, $mapping);
         }
     };
 }
@@ -26,17 +39,48 @@ macro_rules! backtrack_fun {
 
             match *self {
                 $(
-                    $variant => $archetype.backtrack(seq_pair, source_idx, target_idx),
+                    $variant => Operation::<T, C>::backtrack(&$archetype, seq_pair, source_idx, target_idx),
+                )*
+            }
+        }
+    }
+}
+
+macro_rules! unbounded_lookback_fun {
+    ( $op_type:ident, { $($variant:pat => $archetype:expr),* } ) => {
+        fn has_unbounded_lookback(&self) -> bool {
+            use self::$op_type::*;
+
+            match *self {
+                $(
+                    $variant => Operation::<T, C>::has_unbounded_lookback(&$archetype),
                 )*
             }
         }
     }
 }
 
+/// Check whether insertions and deletions have the same cost.
+///
+/// Swapping the source and target sequences swaps insertions and
+/// deletions, so a measure with otherwise symmetric operations is
+/// symmetric when insertions and deletions have the same cost. The
+/// insertion and deletion must be the first two operations.
+macro_rules! indel_symmetric {
+    ( $ops:expr, $op_type:ident ) => {{
+        use self::$op_type::*;
+
+        match (&$ops[0], &$ops[1]) {
+            (Insert(insert_cost), Delete(delete_cost)) => insert_cost == delete_cost,
+            _ => false,
+        }
+    }};
+}
+
 macro_rules! cost_fun {
     ( $op_type:ident, { $($variant:pat => $archetype:expr),* } ) => {
-        fn cost(&self, seq_pair: &SeqPair<T>, cost_matrix: &[Vec<usize>],
-                source_idx: usize, target_idx: usize) -> Option<usize> {
+        fn cost(&self, seq_pair: &SeqPair<T>, cost_matrix: &CostMatrix<C>,
+                source_idx: usize, target_idx: usize) -> Option<C> {
             use self::$op_type::*;
 
             match *self {
@@ -56,53 +100,136 @@ macro_rules! cost_fun {
 /// * Delete
 /// * Substitute
 /// * Match
+///
+/// When the insertion, deletion, and substitution costs are equal,
+/// `Align::distance` uses a bit-parallel algorithm (Myers/Hyyrö) rather
+/// than filling the cost matrix. Elements match when they are equal
+/// according to `Eq`, use `Levenshtein::with_equivalence` to match
+/// elements using another equivalence.
 #[derive(Clone, Debug)]
-pub struct Levenshtein {
-    ops: [LevenshteinOp; 4],
+pub struct Levenshtein<C = usize, E = Equal> {
+    ops: [LevenshteinOp<C, E>; 4],
 }
 
 /// Construct a Levenshtein measure with the associated insertion, deletion,
 /// and substitution cost.
 impl Levenshtein {
     pub fn new(insert_cost: usize, delete_cost: usize, substitute_cost: usize) -> Self {
+        Self::with_costs(insert_cost, delete_cost, substitute_cost)
+    }
+}
+
+impl<C> Levenshtein<C>
+where
+    C: Cost,
+{
+    /// Construct a Levenshtein measure with the associated insertion,
+    /// deletion, and substitution cost of any cost type.
+    pub fn with_costs(insert_cost: C, delete_cost: C, substitute_cost: C) -> Self {
         use self::LevenshteinOp::*;
 
         Levenshtein {
             ops: [
                 Insert(insert_cost),
                 Delete(delete_cost),
-                Match,
+                Match(Equal),
                 Substitute(substitute_cost),
             ],
         }
     }
 }
 
-impl<T> Measure<T> for Levenshtein
+impl<C, E> Levenshtein<C, E>
 where
-    T: Eq,
+    C: Cost,
+{
+    /// Use the given equivalence to decide which elements match.
+    ///
+    /// By default, elements match when they are equal according to `Eq`.
+    pub fn with_equivalence<F>(self, equivalence: F) -> Levenshtein<C, F>
+    where
+        F: Clone,
+    {
+        Levenshtein {
+            ops: [
+                self.ops[0].with_equivalence(&equivalence),
+                self.ops[1].with_equivalence(&equivalence),
+                self.ops[2].with_equivalence(&equivalence),
+                self.ops[3].with_equivalence(&equivalence),
+            ],
+        }
+    }
+}
+
+impl<T, C, E> Measure<T> for Levenshtein<C, E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<T> + Eq + Hash,
 {
-    type Operation = LevenshteinOp;
+    type Cost = C;
+
+    type Operation = LevenshteinOp<C, E>;
 
     fn operations(&self) -> &[Self::Operation] {
         &self.ops
     }
+
+    fn fast_distance(&self, source: &[T], target: &[T]) -> Option<C> {
+        use self::LevenshteinOp::*;
+
+        match self.ops {
+            [Insert(insert_cost), Delete(delete_cost), Match(ref equivalence), Substitute(substitute_cost)]
+                if insert_cost == delete_cost && insert_cost == substitute_cost =>
+            {
+                // Every edit operation has the same cost, so the distance
+                // is the number of edit operations times that cost.
+                let n_edits = myers::levenshtein_distance(source, target, equivalence);
+                Some((0..n_edits).fold(C::zero(), |distance, _| {
+                    distance.saturating_add(insert_cost)
+                }))
+            }
+            _ => None,
+        }
+    }
+
+    fn is_symmetric(&self) -> bool {
+        indel_symmetric!(self.ops, LevenshteinOp)
+    }
 }
 
-/// Levenshtein operation with associated cost.
+/// Levenshtein operation with associated cost or equivalence.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub enum LevenshteinOp {
-    Insert(usize),
-    Delete(usize),
-    Match,
-    Substitute(usize),
+pub enum LevenshteinOp<C = usize, E = Equal> {
+    Insert(C),
+    Delete(C),
+    Match(E),
+    Substitute(C),
+}
+
+impl<C, E> LevenshteinOp<C, E>
+where
+    C: Cost,
+{
+    fn with_equivalence<F>(&self, equivalence: &F) -> LevenshteinOp<C, F>
+    where
+        F: Clone,
+    {
+        use self::LevenshteinOp::*;
+
+        match *self {
+            Insert(cost) => Insert(cost),
+            Delete(cost) => Delete(cost),
+            Match(_) => Match(equivalence.clone()),
+            Substitute(cost) => Substitute(cost),
+        }
+    }
 }
 
 op_mapping!(LevenshteinOp, {
-    Delete(cost)     => archetype::Delete(cost),
-    Insert(cost)     => archetype::Insert(cost),
-    Match            => archetype::Match,
-    Substitute(cost) => archetype::Substitute(cost)
+    Delete(cost)           => archetype::Delete(cost),
+    Insert(cost)           => archetype::Insert(cost),
+    Match(ref equivalence) => archetype::MatchWith(equivalence),
+    Substitute(cost)       => archetype::Substitute(cost)
 });
 
 /// Levenshtein-Damerau distance.
@@ -114,9 +241,17 @@ op_mapping!(LevenshteinOp, {
 /// * Substitute
 /// * Match
 /// * Transpose (*xy* -> *yx*)
+///
+/// This is the optimal string alignment variant of Levenshtein-Damerau
+/// distance: transposed elements cannot be edited further. For example,
+/// the distance between *CA* and *ABC* is *3* rather than *2*. Use
+/// `UnrestrictedLevenshteinDamerau` for the variant that satisfies the
+/// triangle inequality. Elements are matched and transposed when they are
+/// equal according to `Eq`, use `LevenshteinDamerau::with_equivalence` to
+/// compare elements using another equivalence.
 #[derive(Clone, Debug)]
-pub struct LevenshteinDamerau {
-    ops: [LevenshteinDamerauOp; 5],
+pub struct LevenshteinDamerau<C = usize, E = Equal> {
+    ops: [LevenshteinDamerauOp<C, E>; 5],
 }
 
 /// Construct a Levenshtein-Damerau measure with the associated insertion,
@@ -127,6 +262,23 @@ impl LevenshteinDamerau {
         delete_cost: usize,
         substitute_cost: usize,
         transpose_cost: usize,
+    ) -> Self {
+        Self::with_costs(insert_cost, delete_cost, substitute_cost, transpose_cost)
+    }
+}
+
+impl<C> LevenshteinDamerau<C>
+where
+    C: Cost,
+{
+    /// Construct a Levenshtein-Damerau measure with the associated
+    /// insertion, deletion, substitution, and transposition cost of any
+    /// cost type.
+    pub fn with_costs(
+        insert_cost: C,
+        delete_cost: C,
+        substitute_cost: C,
+        transpose_cost: C,
     ) -> Self {
         use self::LevenshteinDamerauOp::*;
 
@@ -134,41 +286,287 @@ impl LevenshteinDamerau {
             ops: [
                 Insert(insert_cost),
                 Delete(delete_cost),
-                Match,
+                Match(Equal),
                 Substitute(substitute_cost),
-                Transpose(transpose_cost),
+                Transpose(transpose_cost, Equal),
             ],
         }
     }
 }
 
-impl<T> Measure<T> for LevenshteinDamerau
+impl<C, E> LevenshteinDamerau<C, E>
 where
-    T: Eq,
+    C: Cost,
+{
+    /// Use the given equivalence to decide which elements match and can
+    /// be transposed.
+    ///
+    /// By default, elements are compared using `Eq`.
+    pub fn with_equivalence<F>(self, equivalence: F) -> LevenshteinDamerau<C, F>
+    where
+        F: Clone,
+    {
+        LevenshteinDamerau {
+            ops: [
+                self.ops[0].with_equivalence(&equivalence),
+                self.ops[1].with_equivalence(&equivalence),
+                self.ops[2].with_equivalence(&equivalence),
+                self.ops[3].with_equivalence(&equivalence),
+                self.ops[4].with_equivalence(&equivalence),
+            ],
+        }
+    }
+}
+
+impl<T, C, E> Measure<T> for LevenshteinDamerau<C, E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<T> + Eq + Hash,
 {
-    type Operation = LevenshteinDamerauOp;
+    type Cost = C;
+
+    type Operation = LevenshteinDamerauOp<C, E>;
 
     fn operations(&self) -> &[Self::Operation] {
         &self.ops
     }
+
+    fn is_symmetric(&self) -> bool {
+        indel_symmetric!(self.ops, LevenshteinDamerauOp)
+    }
 }
 
-/// Levenshtein operation with associated cost.
+/// Levenshtein-Damerau operation with associated cost or equivalence.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub enum LevenshteinDamerauOp {
-    Insert(usize),
-    Delete(usize),
-    Match,
-    Substitute(usize),
-    Transpose(usize),
+pub enum LevenshteinDamerauOp<C = usize, E = Equal> {
+    Insert(C),
+    Delete(C),
+    Match(E),
+    Substitute(C),
+    Transpose(C, E),
+}
+
+impl<C, E> LevenshteinDamerauOp<C, E>
+where
+    C: Cost,
+{
+    fn with_equivalence<F>(&self, equivalence: &F) -> LevenshteinDamerauOp<C, F>
+    where
+        F: Clone,
+    {
+        use self::LevenshteinDamerauOp::*;
+
+        match *self {
+            Insert(cost) => Insert(cost),
+            Delete(cost) => Delete(cost),
+            Match(_) => Match(equivalence.clone()),
+            Substitute(cost) => Substitute(cost),
+            Transpose(cost, _) => Transpose(cost, equivalence.clone()),
+        }
+    }
 }
 
 op_mapping!(LevenshteinDamerauOp, {
-    Delete(cost)     => archetype::Delete(cost),
-    Insert(cost)     => archetype::Insert(cost),
-    Match            => archetype::Match,
-    Substitute(cost) => archetype::Substitute(cost),
-    Transpose(cost)  => archetype::Transpose(cost)
+    Delete(cost)                     => archetype::Delete(cost),
+    Insert(cost)                     => archetype::Insert(cost),
+    Match(ref equivalence)           => archetype::MatchWith(equivalence),
+    Substitute(cost)                 => archetype::Substitute(cost),
+    Transpose(cost, ref equivalence) => archetype::TransposeWith(cost, equivalence)
+});
+
+/// Unrestricted Levenshtein-Damerau distance.
+///
+/// Unrestricted Levenshtein-Damerau distance uses the following
+/// operations:
+///
+/// * Insert
+/// * Delete
+/// * Substitute
+/// * Match
+/// * Transpose (*x...y* -> *y...x*)
+///
+/// In contrast to `LevenshteinDamerau`, elements can be inserted and
+/// deleted between transposed elements. For example, *CA* can be
+/// rewritten to *ABC* with a transposition and an insertion. The cost of
+/// a transposition is the transposition cost plus the costs of the
+/// deletions and insertions between the transposed elements (see
+/// `archetype::UnrestrictedTranspose`). This distance is a metric when
+/// the insertion and deletion costs are equal and twice the
+/// transposition cost is at least the insertion cost plus the deletion
+/// cost. Elements are compared using `Eq`, use
+/// `UnrestrictedLevenshteinDamerau::with_equivalence` to compare elements
+/// using another equivalence.
+///
+/// Transpositions can span any number of elements, so this measure
+/// cannot be used with `HirschbergAlign` and `Align::distance` uses the
+/// full cost matrix.
+///
+/// ```
+/// use seqalign::Align;
+/// use seqalign::measures::{LevenshteinDamerau, UnrestrictedLevenshteinDamerau};
+///
+/// let ca = &['C', 'A'];
+/// let abc = &['A', 'B', 'C'];
+///
+/// let osa = LevenshteinDamerau::new(1, 1, 1, 1);
+/// assert_eq!(osa.distance(ca, abc), 3);
+///
+/// let unrestricted = UnrestrictedLevenshteinDamerau::new(1, 1, 1, 1);
+/// assert_eq!(unrestricted.distance(ca, abc), 2);
+/// ```
+#[derive(Clone, Debug)]
+pub struct UnrestrictedLevenshteinDamerau<C = usize, E = Equal> {
+    ops: [UnrestrictedLevenshteinDamerauOp<C, E>; 5],
+}
+
+/// Construct an unrestricted Levenshtein-Damerau measure with the
+/// associated insertion, deletion, substitution, and transposition cost.
+impl UnrestrictedLevenshteinDamerau {
+    pub fn new(
+        insert_cost: usize,
+        delete_cost: usize,
+        substitute_cost: usize,
+        transpose_cost: usize,
+    ) -> Self {
+        Self::with_costs(insert_cost, delete_cost, substitute_cost, transpose_cost)
+    }
+}
+
+impl<C> UnrestrictedLevenshteinDamerau<C>
+where
+    C: Cost,
+{
+    /// Construct an unrestricted Levenshtein-Damerau measure with the
+    /// associated insertion, deletion, substitution, and transposition
+    /// cost of any cost type.
+    pub fn with_costs(
+        insert_cost: C,
+        delete_cost: C,
+        substitute_cost: C,
+        transpose_cost: C,
+    ) -> Self {
+        use self::UnrestrictedLevenshteinDamerauOp::*;
+
+        UnrestrictedLevenshteinDamerau {
+            ops: [
+                Insert(insert_cost),
+                Delete(delete_cost),
+                Match(Equal),
+                Substitute(substitute_cost),
+                Transpose {
+                    transpose_cost,
+                    insert_cost,
+                    delete_cost,
+                    equivalence: Equal,
+                },
+            ],
+        }
+    }
+}
+
+impl<C, E> UnrestrictedLevenshteinDamerau<C, E>
+where
+    C: Cost,
+{
+    /// Use the given equivalence to decide which elements match and can
+    /// be transposed.
+    ///
+    /// By default, elements are compared using `Eq`.
+    pub fn with_equivalence<F>(self, equivalence: F) -> UnrestrictedLevenshteinDamerau<C, F>
+    where
+        F: Clone,
+    {
+        UnrestrictedLevenshteinDamerau {
+            ops: [
+                self.ops[0].with_equivalence(&equivalence),
+                self.ops[1].with_equivalence(&equivalence),
+                self.ops[2].with_equivalence(&equivalence),
+                self.ops[3].with_equivalence(&equivalence),
+                self.ops[4].with_equivalence(&equivalence),
+            ],
+        }
+    }
+}
+
+impl<T, C, E> Measure<T> for UnrestrictedLevenshteinDamerau<C, E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<T> + Eq + Hash,
+{
+    type Cost = C;
+
+    type Operation = UnrestrictedLevenshteinDamerauOp<C, E>;
+
+    fn operations(&self) -> &[Self::Operation] {
+        &self.ops
+    }
+
+    fn is_symmetric(&self) -> bool {
+        indel_symmetric!(self.ops, UnrestrictedLevenshteinDamerauOp)
+    }
+}
+
+/// Unrestricted Levenshtein-Damerau operation with associated cost or
+/// equivalence.
+///
+/// The transposition also stores the insertion and deletion cost, which
+/// are charged for the elements between the transposed elements.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum UnrestrictedLevenshteinDamerauOp<C = usize, E = Equal> {
+    Insert(C),
+    Delete(C),
+    Match(E),
+    Substitute(C),
+    Transpose {
+        transpose_cost: C,
+        insert_cost: C,
+        delete_cost: C,
+        equivalence: E,
+    },
+}
+
+impl<C, E> UnrestrictedLevenshteinDamerauOp<C, E>
+where
+    C: Cost,
+{
+    fn with_equivalence<F>(&self, equivalence: &F) -> UnrestrictedLevenshteinDamerauOp<C, F>
+    where
+        F: Clone,
+    {
+        use self::UnrestrictedLevenshteinDamerauOp::*;
+
+        match *self {
+            Insert(cost) => Insert(cost),
+            Delete(cost) => Delete(cost),
+            Match(_) => Match(equivalence.clone()),
+            Substitute(cost) => Substitute(cost),
+            Transpose {
+                transpose_cost,
+                insert_cost,
+                delete_cost,
+                ..
+            } => Transpose {
+                transpose_cost,
+                insert_cost,
+                delete_cost,
+                equivalence: equivalence.clone(),
+            },
+        }
+    }
+}
+
+op_mapping!(UnrestrictedLevenshteinDamerauOp, {
+    Delete(cost)           => archetype::Delete(cost),
+    Insert(cost)           => archetype::Insert(cost),
+    Match(ref equivalence) => archetype::MatchWith(equivalence),
+    Substitute(cost)       => archetype::Substitute(cost),
+    Transpose { transpose_cost, insert_cost, delete_cost, ref equivalence } =>
+        archetype::UnrestrictedTransposeWith {
+            transpose_cost,
+            insert_cost,
+            delete_cost,
+            equivalence
+        }
 });
 
 /// Longest common subsequence (LCS) alignment.
@@ -181,53 +579,596 @@ op_mapping!(LevenshteinDamerauOp, {
 ///
 /// The matches in edit script for this measure give a longest common
 /// subsequence. The cost is the number of insertions/deletions after
-/// aligning the LCSes.
+/// aligning the LCSes. Elements match when they are equal according to
+/// `Eq`, use `LCS::with_equivalence` to match elements using another
+/// equivalence.
 #[derive(Clone, Debug)]
-pub struct LCS {
-    ops: [LCSOp; 3],
+pub struct LCS<C = usize, E = Equal> {
+    ops: [LCSOp<C, E>; 3],
 }
 
 /// Construct LCS measure with the associated insertion and deletion
 /// cost.
 impl LCS {
     pub fn new(insert_cost: usize, delete_cost: usize) -> Self {
+        Self::with_costs(insert_cost, delete_cost)
+    }
+}
+
+impl<C> LCS<C>
+where
+    C: Cost,
+{
+    /// Construct LCS measure with the associated insertion and deletion
+    /// cost of any cost type.
+    pub fn with_costs(insert_cost: C, delete_cost: C) -> Self {
         use self::LCSOp::*;
 
         LCS {
-            ops: [Insert(insert_cost), Delete(delete_cost), Match],
+            ops: [Insert(insert_cost), Delete(delete_cost), Match(Equal)],
         }
     }
 }
 
-impl<T> Measure<T> for LCS
+impl<C, E> LCS<C, E>
 where
-    T: Eq,
+    C: Cost,
+{
+    /// Use the given equivalence to decide which elements match.
+    ///
+    /// By default, elements match when they are equal according to `Eq`.
+    pub fn with_equivalence<F>(self, equivalence: F) -> LCS<C, F>
+    where
+        F: Clone,
+    {
+        LCS {
+            ops: [
+                self.ops[0].with_equivalence(&equivalence),
+                self.ops[1].with_equivalence(&equivalence),
+                self.ops[2].with_equivalence(&equivalence),
+            ],
+        }
+    }
+}
+
+impl<T, C, E> Measure<T> for LCS<C, E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<T> + Eq + Hash,
 {
-    type Operation = LCSOp;
+    type Cost = C;
+
+    type Operation = LCSOp<C, E>;
 
     fn operations(&self) -> &[Self::Operation] {
         &self.ops
     }
+
+    fn is_symmetric(&self) -> bool {
+        indel_symmetric!(self.ops, LCSOp)
+    }
 }
 
-/// Levenshtein operation with associated cost.
+/// LCS operation with associated cost or equivalence.
 #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
-pub enum LCSOp {
-    Insert(usize),
-    Delete(usize),
-    Match,
+pub enum LCSOp<C = usize, E = Equal> {
+    Insert(C),
+    Delete(C),
+    Match(E),
+}
+
+impl<C, E> LCSOp<C, E>
+where
+    C: Cost,
+{
+    fn with_equivalence<F>(&self, equivalence: &F) -> LCSOp<C, F>
+    where
+        F: Clone,
+    {
+        use self::LCSOp::*;
+
+        match *self {
+            Insert(cost) => Insert(cost),
+            Delete(cost) => Delete(cost),
+            Match(_) => Match(equivalence.clone()),
+        }
+    }
 }
 
 op_mapping!(LCSOp, {
-    Delete(cost)     => archetype::Delete(cost),
-    Insert(cost)     => archetype::Insert(cost),
-    Match            => archetype::Match
+    Delete(cost)           => archetype::Delete(cost),
+    Insert(cost)           => archetype::Insert(cost),
+    Match(ref equivalence) => archetype::MatchWith(equivalence)
 });
 
+/// Hamming distance.
+///
+/// This measure uses the following edit operations:
+///
+/// * Substitute
+/// * Match
+///
+/// Since there are no insertions or deletions, the *i*-th element of the
+/// source sequence is always aligned with the *i*-th element of the
+/// target sequence. Consequently, only sequences of the same length can
+/// be aligned. Alignment is done in linear time without a cost matrix, so
+/// this measure does not implement `Measure`, alignments are computed
+/// with `Hamming::align`. Elements match when they are equal according to
+/// `Eq`, use `Hamming::with_equivalence` to match elements using another
+/// equivalence.
+///
+/// ```
+/// use seqalign::measures::Hamming;
+/// use seqalign::AlignError;
+///
+/// let hamming = Hamming::new(1);
+///
+/// let source = &['A', 'C', 'G', 'T'];
+/// let target = &['A', 'G', 'G', 'A'];
+/// assert_eq!(hamming.distance(source, target), Ok(2));
+///
+/// assert_eq!(
+///     hamming.distance(source, &target[..3]),
+///     Err(AlignError::LengthMismatch {
+///         source_len: 4,
+///         target_len: 3
+///     })
+/// );
+/// ```
+#[derive(Clone, Debug)]
+pub struct Hamming<C = usize, E = Equal> {
+    substitute_cost: C,
+    equivalence: E,
+}
+
+/// Construct a Hamming measure with the associated substitution cost.
+impl Hamming {
+    pub fn new(substitute_cost: usize) -> Self {
+        Self::with_costs(substitute_cost)
+    }
+}
+
+impl<C> Hamming<C>
+where
+    C: Cost,
+{
+    /// Construct a Hamming measure with the associated substitution cost
+    /// of any cost type.
+    pub fn with_costs(substitute_cost: C) -> Self {
+        Hamming {
+            substitute_cost,
+            equivalence: Equal,
+        }
+    }
+}
+
+impl<C, E> Hamming<C, E>
+where
+    C: Cost,
+{
+    /// Use the given equivalence to decide which elements match.
+    ///
+    /// By default, elements match when they are equal according to `Eq`.
+    pub fn with_equivalence<F>(self, equivalence: F) -> Hamming<C, F> {
+        Hamming {
+            substitute_cost: self.substitute_cost,
+            equivalence,
+        }
+    }
+
+    /// Align two sequences.
+    ///
+    /// Returns an error if the sequences do not have the same length.
+    pub fn align<'a, T>(
+        &'a self,
+        source: &'a [T],
+        target: &'a [T],
+    ) -> Result<HammingAlignment<'a, T, C, E>, AlignError>
+    where
+        E: Equivalence<T>,
+    {
+        Ok(HammingAlignment {
+            distance: self.distance(source, target)?,
+            pair: SeqPair { source, target },
+            measure: self,
+        })
+    }
+
+    /// Compute the edit distance of two sequences.
+    ///
+    /// Returns an error if the sequences do not have the same length.
+    pub fn distance<T>(&self, source: &[T], target: &[T]) -> Result<C, AlignError>
+    where
+        E: Equivalence<T>,
+    {
+        if source.len() != target.len() {
+            return Err(AlignError::LengthMismatch {
+                source_len: source.len(),
+                target_len: target.len(),
+            });
+        }
+
+        Ok(source
+            .iter()
+            .zip(target)
+            .filter(|(source_elem, target_elem)| {
+                !self.equivalence.equivalent(source_elem, target_elem)
+            })
+            .fold(C::zero(), |distance, _| {
+                distance.saturating_add(self.substitute_cost)
+            }))
+    }
+}
+
+/// Hamming operation with associated cost.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum HammingOp<C = usize> {
+    Match,
+    Substitute(C),
+}
+
+/// Hamming alignment of two sequences.
+pub struct HammingAlignment<'a, T, C = usize, E = Equal> {
+    measure: &'a Hamming<C, E>,
+    pair: SeqPair<'a, T>,
+    distance: C,
+}
+
+impl<'a, T, C, E> HammingAlignment<'a, T, C, E>
+where
+    C: Cost,
+    E: Equivalence<T>,
+{
+    /// Get the edit distance.
+    pub fn distance(&self) -> C {
+        self.distance
+    }
+
+    /// Return the script of edit operations to rewrite the source sequence
+    /// to the target sequence.
+    pub fn edit_script(&self) -> EditScript<HammingOp<C>> {
+        self.pair
+            .source
+            .iter()
+            .zip(self.pair.target)
+            .enumerate()
+            .map(|(idx, (source_elem, target_elem))| {
+                let op = if self
+                    .measure
+                    .equivalence
+                    .equivalent(source_elem, target_elem)
+                {
+                    HammingOp::Match
+                } else {
+                    HammingOp::Substitute(self.measure.substitute_cost)
+                };
+                IndexedOperation::new(op, idx, idx)
+            })
+            .collect()
+    }
+
+    /// Get the sequence pair associated with this alignment.
+    pub fn seq_pair(&self) -> &SeqPair<'_, T> {
+        &self.pair
+    }
+}
+
+/// Levenshtein distance with element-dependent costs.
+///
+/// This measure uses the same operations as `Levenshtein`, but the costs
+/// of insertions, deletions, and substitutions are given by an
+/// `ElementCosts` implementation. For instance, in OCR post-correction
+/// substituting *l* by *1* can be made cheaper than substituting *l* by
+/// *w*. The costs that were charged for the operations in an edit script
+/// can be retrieved with `Alignment::edit_script_with_costs`.
+///
+/// ```
+/// use seqalign::Align;
+/// use seqalign::cost::CostFns;
+/// use seqalign::measures::WeightedLevenshtein;
+///
+/// let costs = CostFns::new(
+///     |_: &char| 2usize,
+///     |_: &char| 2,
+///     |&source: &char, &target: &char| match (source, target) {
+///         ('l', '1') | ('1', 'l') => 1,
+///         _ => 3,
+///     },
+/// );
+/// let measure = WeightedLevenshtein::new(costs);
+///
+/// let source: Vec<char> = "he1lo".chars().collect();
+/// let target: Vec<char> = "hello".chars().collect();
+/// assert_eq!(measure.align(&source, &target).distance(), 1);
+/// ```
+#[derive(Clone, Debug)]
+pub struct WeightedLevenshtein<F> {
+    ops: [WeightedLevenshteinOp<F>; 4],
+}
+
+impl<F> WeightedLevenshtein<F>
+where
+    F: Clone,
+{
+    /// Construct a weighted Levenshtein measure with the given element
+    /// costs.
+    pub fn new(costs: F) -> Self {
+        use self::WeightedLevenshteinOp::*;
+
+        WeightedLevenshtein {
+            ops: [
+                Insert(costs.clone()),
+                Delete(costs.clone()),
+                Match,
+                Substitute(costs),
+            ],
+        }
+    }
+}
+
+impl<T, F> Measure<T> for WeightedLevenshtein<F>
+where
+    F: Clone + Debug + ElementCosts<T> + Eq + Hash,
+    T: Eq,
+{
+    type Cost = F::Cost;
+
+    type Operation = WeightedLevenshteinOp<F>;
+
+    fn operations(&self) -> &[Self::Operation] {
+        &self.ops
+    }
+}
+
+/// Weighted Levenshtein operation with associated element costs.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum WeightedLevenshteinOp<F> {
+    Insert(F),
+    Delete(F),
+    Match,
+    Substitute(F),
+}
+
+impl<T, F> Operation<T, F::Cost> for WeightedLevenshteinOp<F>
+where
+    F: Clone + Debug + ElementCosts<T> + Eq + Hash,
+    T: Eq,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<T>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        use self::WeightedLevenshteinOp::*;
+
+        match self {
+            Insert(costs) => {
+                archetype::InsertWith(costs).backtrack(seq_pair, source_idx, target_idx)
+            }
+            Delete(costs) => {
+                archetype::DeleteWith(costs).backtrack(seq_pair, source_idx, target_idx)
+            }
+            Match => Operation::<T, F::Cost>::backtrack(
+                &archetype::Match,
+                seq_pair,
+                source_idx,
+                target_idx,
+            ),
+            Substitute(costs) => {
+                archetype::SubstituteWith(costs).backtrack(seq_pair, source_idx, target_idx)
+            }
+        }
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<T>,
+        cost_matrix: &CostMatrix<F::Cost>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<F::Cost> {
+        use self::WeightedLevenshteinOp::*;
+
+        match self {
+            Insert(costs) => {
+                archetype::InsertWith(costs).cost(seq_pair, cost_matrix, source_idx, target_idx)
+            }
+            Delete(costs) => {
+                archetype::DeleteWith(costs).cost(seq_pair, cost_matrix, source_idx, target_idx)
+            }
+            Match => archetype::Match.cost(seq_pair, cost_matrix, source_idx, target_idx),
+            Substitute(costs) => {
+                archetype::SubstituteWith(costs).cost(seq_pair, cost_matrix, source_idx, target_idx)
+            }
+        }
+    }
+}
+
+/// Levenshtein distance with substitution costs from a substitution
+/// matrix.
+///
+/// This measure uses the same operations as `Levenshtein`. The cost of a
+/// match or substitution of *a* by *b* is derived from the similarity
+/// score in the substitution matrix as *max_score - score(a, b)*, where
+/// *max_score* is the highest score in the matrix. Insertions and
+/// deletions have fixed costs.
+///
+/// Since the cost of matching *a* is *max_score - score(a, a)*, matches
+/// are only free for the symbols with the highest score. In the example
+/// below, the matches of the identical symbols sum to *48* with BLOSUM62
+/// and substituting *W* by *Y* costs *9*, giving a distance of *57*.
+///
+/// Sequences with symbols that are not in the substitution matrix cannot
+/// be aligned. `Align::try_align` returns `AlignError::UnknownSymbol` for
+/// such sequences, `Align::align` and `Align::distance` panic.
+///
+/// ```
+/// use seqalign::Align;
+/// use seqalign::measures::MatrixLevenshtein;
+/// use seqalign::SubstitutionMatrix;
+///
+/// let measure = MatrixLevenshtein::new(SubstitutionMatrix::blosum62(), 12, 12);
+///
+/// let source: Vec<char> = "HEAGAWGHEE".chars().collect();
+/// let target: Vec<char> = "HEAGAYGHEE".chars().collect();
+/// assert_eq!(measure.align(&source, &target).distance(), 57);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MatrixLevenshtein {
+    matrix: Arc<SubstitutionMatrix>,
+    ops: [MatrixLevenshteinOp; 4],
+}
+
+impl MatrixLevenshtein {
+    /// Construct a measure with the given substitution matrix and
+    /// insertion and deletion cost.
+    pub fn new(matrix: SubstitutionMatrix, insert_cost: usize, delete_cost: usize) -> Self {
+        use self::MatrixLevenshteinOp::*;
+
+        let matrix = Arc::new(matrix);
+
+        MatrixLevenshtein {
+            ops: [
+                Insert(insert_cost),
+                Delete(delete_cost),
+                Match(matrix.clone()),
+                Substitute(matrix.clone()),
+            ],
+            matrix,
+        }
+    }
+
+    /// Get the substitution matrix.
+    pub fn matrix(&self) -> &SubstitutionMatrix {
+        &self.matrix
+    }
+}
+
+impl Measure<char> for MatrixLevenshtein {
+    type Cost = usize;
+
+    type Operation = MatrixLevenshteinOp;
+
+    fn operations(&self) -> &[Self::Operation] {
+        &self.ops
+    }
+
+    fn is_symmetric(&self) -> bool {
+        indel_symmetric!(self.ops, MatrixLevenshteinOp) && self.matrix.is_symmetric()
+    }
+
+    fn check_sequences(&self, source: &[char], target: &[char]) -> Result<(), AlignError> {
+        self.matrix.check_symbols(source)?;
+        self.matrix.check_symbols(target)
+    }
+}
+
+/// Substitution matrix Levenshtein operation with associated cost or
+/// substitution matrix.
+#[derive(Clone, Eq, Hash, PartialEq)]
+pub enum MatrixLevenshteinOp {
+    Insert(usize),
+    Delete(usize),
+    Match(Arc<SubstitutionMatrix>),
+    Substitute(Arc<SubstitutionMatrix>),
+}
+
+impl MatrixLevenshteinOp {
+    fn matrix_cost(matrix: &SubstitutionMatrix, source: char, target: char) -> Option<usize> {
+        let score = matrix.score(source, target).ok()?;
+        Some((matrix.max_score() - score) as usize)
+    }
+}
+
+// The substitution matrix is left out, since it would clutter the
+// debug output of edit scripts.
+impl fmt::Debug for MatrixLevenshteinOp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::MatrixLevenshteinOp::*;
+
+        match self {
+            Insert(cost) => f.debug_tuple("Insert").field(cost).finish(),
+            Delete(cost) => f.debug_tuple("Delete").field(cost).finish(),
+            Match(_) => f.write_str("Match"),
+            Substitute(_) => f.write_str("Substitute"),
+        }
+    }
+}
+
+impl Operation<char, usize> for MatrixLevenshteinOp {
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<char>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        use self::MatrixLevenshteinOp::*;
+
+        match self {
+            Insert(cost) => archetype::Insert(*cost).backtrack(seq_pair, source_idx, target_idx),
+            Delete(cost) => archetype::Delete(*cost).backtrack(seq_pair, source_idx, target_idx),
+            Match(_) => Operation::<char, usize>::backtrack(
+                &archetype::Match,
+                seq_pair,
+                source_idx,
+                target_idx,
+            ),
+            Substitute(_) => {
+                archetype::Substitute(0usize).backtrack(seq_pair, source_idx, target_idx)
+            }
+        }
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<char>,
+        cost_matrix: &CostMatrix<usize>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<usize> {
+        use self::MatrixLevenshteinOp::*;
+
+        match self {
+            Insert(cost) => {
+                archetype::Insert(*cost).cost(seq_pair, cost_matrix, source_idx, target_idx)
+            }
+            Delete(cost) => {
+                archetype::Delete(*cost).cost(seq_pair, cost_matrix, source_idx, target_idx)
+            }
+            Match(matrix) | Substitute(matrix) => {
+                let (from_source_idx, from_target_idx) =
+                    self.backtrack(seq_pair, source_idx, target_idx)?;
+                let source = seq_pair.source[from_source_idx];
+                let target = seq_pair.target[from_target_idx];
+
+                // Equal symbols are matched, other symbols are substituted.
+                let is_match = match self {
+                    Match(_) => true,
+                    _ => false,
+                };
+                if (source == target) != is_match {
+                    return None;
+                }
+
+                let op_cost = Self::matrix_cost(matrix, source, target)?;
+                Some(cost_matrix[(from_source_idx, from_target_idx)].saturating_add(op_cost))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::measures::{Levenshtein, LevenshteinDamerau, LCS};
-    use crate::Measure;
+    use crate::cost::{CostFns, OrderedFloat, Saturating};
+    use crate::equivalence::{Equal, EquivalenceFn};
+    use crate::measures::{
+        Hamming, HammingOp, Levenshtein, LevenshteinDamerau, MatrixLevenshtein,
+        MatrixLevenshteinOp, UnrestrictedLevenshteinDamerau, UnrestrictedLevenshteinDamerauOp,
+        WeightedLevenshtein, WeightedLevenshteinOp, LCS,
+    };
+    use crate::op::IndexedOperation;
+    use crate::{AlignError, Measure, SubstitutionMatrix};
 
     use crate::Align;
 
@@ -236,6 +1177,7 @@ mod tests {
         target: &'static str,
         levenshtein_dist: usize,
         levenshtein_damerau_dist: usize,
+        unrestricted_damerau_dist: usize,
         lcs_dist: usize,
     }
 
@@ -245,6 +1187,7 @@ mod tests {
             target: &'static str,
             levenshtein_dist: usize,
             levenshtein_damerau_dist: usize,
+            unrestricted_damerau_dist: usize,
             lcs_dist: usize,
         ) -> Self {
             TestCase {
@@ -252,6 +1195,7 @@ mod tests {
                 target,
                 levenshtein_dist,
                 levenshtein_damerau_dist,
+                unrestricted_damerau_dist,
                 lcs_dist,
             }
         }
@@ -259,13 +1203,15 @@ mod tests {
 
     lazy_static! {
         static ref TESTCASES: Vec<TestCase> = vec![
-            TestCase::new("pineapple", "", 9, 9, 9),
-            TestCase::new("", "pineapple", 9, 9, 9),
-            TestCase::new("pineapple", "pen", 7, 7, 8),
-            TestCase::new("pen", "pineapple", 7, 7, 8),
-            TestCase::new("pineapple", "applet", 5, 5, 5),
-            TestCase::new("applet", "pen", 4, 4, 5),
-            TestCase::new("tpyo", "typo", 2, 1, 2),
+            TestCase::new("pineapple", "", 9, 9, 9, 9),
+            TestCase::new("", "pineapple", 9, 9, 9, 9),
+            TestCase::new("pineapple", "pen", 7, 7, 7, 8),
+            TestCase::new("pen", "pineapple", 7, 7, 7, 8),
+            TestCase::new("pineapple", "applet", 5, 5, 5, 5),
+            TestCase::new("applet", "pen", 4, 4, 4, 5),
+            TestCase::new("tpyo", "typo", 2, 1, 1, 2),
+            TestCase::new("ca", "abc", 3, 3, 2, 3),
+            TestCase::new("abc", "ca", 3, 3, 2, 3),
         ];
     }
 
@@ -274,6 +1220,167 @@ mod tests {
         run_testcases(|| LCS::new(1, 1), |testcase| testcase.lcs_dist);
     }
 
+    #[test]
+    pub fn test_equivalence() {
+        // With case-insensitive matching, uppercasing the source does not
+        // change the distances.
+        let case_insensitive = EquivalenceFn::new(|a: &char, b: &char| a.eq_ignore_ascii_case(b));
+        let levenshtein = Levenshtein::new(1, 1, 1).with_equivalence(case_insensitive.clone());
+        let levenshtein_damerau =
+            LevenshteinDamerau::new(1, 1, 1, 1).with_equivalence(case_insensitive.clone());
+        let unrestricted_damerau = UnrestrictedLevenshteinDamerau::new(1, 1, 1, 1)
+            .with_equivalence(case_insensitive.clone());
+        let lcs = LCS::new(1, 1).with_equivalence(case_insensitive);
+
+        for testcase in TESTCASES.iter() {
+            let source: Vec<char> = testcase.source.to_uppercase().chars().collect();
+            let target: Vec<char> = testcase.target.chars().collect();
+            assert_eq!(
+                levenshtein.distance(&source, &target),
+                testcase.levenshtein_dist
+            );
+            assert_eq!(
+                levenshtein.align(&source, &target).distance(),
+                testcase.levenshtein_dist
+            );
+            assert_eq!(
+                levenshtein_damerau.distance(&source, &target),
+                testcase.levenshtein_damerau_dist
+            );
+            assert_eq!(
+                unrestricted_damerau.distance(&source, &target),
+                testcase.unrestricted_damerau_dist
+            );
+            assert_eq!(lcs.distance(&source, &target), testcase.lcs_dist);
+        }
+    }
+
+    #[test]
+    pub fn test_weighted_levenshtein() {
+        run_testcases(
+            || {
+                WeightedLevenshtein::new(CostFns::new(
+                    |_: &char| 1,
+                    |_: &char| 1,
+                    |_: &char, _: &char| 1,
+                ))
+            },
+            |testcase| testcase.levenshtein_dist,
+        );
+    }
+
+    #[test]
+    pub fn test_weighted_levenshtein_costs() {
+        use WeightedLevenshteinOp::*;
+
+        // OCR confusions are cheaper than other substitutions.
+        let costs = CostFns::new(
+            |_: &char| 3usize,
+            |_: &char| 3,
+            |&source: &char, &target: &char| match (source, target) {
+                ('l', '1') | ('1', 'l') | ('o', '0') | ('0', 'o') => 1,
+                _ => 4,
+            },
+        );
+        let measure = WeightedLevenshtein::new(costs);
+
+        let world: Vec<char> = "world".chars().collect();
+        let w0r1d: Vec<char> = "w0r1d".chars().collect();
+        let worwd: Vec<char> = "worwd".chars().collect();
+        let word: Vec<char> = "word".chars().collect();
+
+        let alignment = measure.align(&w0r1d, &world);
+        assert_eq!(alignment.distance(), 2);
+        let costs: Vec<_> = alignment
+            .edit_script_with_costs()
+            .into_iter()
+            .map(|(op, cost)| {
+                let name = match op.operation() {
+                    Insert(_) => "insert",
+                    Delete(_) => "delete",
+                    Match => "match",
+                    Substitute(_) => "substitute",
+                };
+                (name, cost)
+            })
+            .collect();
+        assert_eq!(
+            costs,
+            vec![
+                ("match", 0),
+                ("substitute", 1),
+                ("match", 0),
+                ("substitute", 1),
+                ("match", 0)
+            ]
+        );
+
+        assert_eq!(measure.align(&worwd, &world).distance(), 4);
+        assert_eq!(measure.distance(&word, &world), 3);
+    }
+
+    #[test]
+    pub fn test_hamming() {
+        use HammingOp::*;
+
+        let hamming = Hamming::new(2);
+
+        let karolin: Vec<char> = "karolin".chars().collect();
+        let kathrin: Vec<char> = "kathrin".chars().collect();
+        let alignment = hamming.align(&karolin, &kathrin).unwrap();
+        assert_eq!(alignment.distance(), 6);
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(Match, 0, 0),
+                IndexedOperation::new(Match, 1, 1),
+                IndexedOperation::new(Substitute(2), 2, 2),
+                IndexedOperation::new(Substitute(2), 3, 3),
+                IndexedOperation::new(Substitute(2), 4, 4),
+                IndexedOperation::new(Match, 5, 5),
+                IndexedOperation::new(Match, 6, 6),
+            ]
+        );
+
+        let empty: &[char] = &[];
+        assert_eq!(hamming.distance(empty, empty), Ok(0));
+        assert!(hamming
+            .align(empty, empty)
+            .unwrap()
+            .edit_script()
+            .is_empty());
+
+        let karolin_upper: Vec<char> = "KAROLIN".chars().collect();
+        let case_insensitive =
+            hamming
+                .clone()
+                .with_equivalence(EquivalenceFn::new(|a: &char, b: &char| {
+                    a.eq_ignore_ascii_case(b)
+                }));
+        assert_eq!(hamming.distance(&karolin_upper, &kathrin), Ok(14));
+        assert_eq!(case_insensitive.distance(&karolin_upper, &kathrin), Ok(6));
+        assert_eq!(
+            case_insensitive
+                .align(&karolin_upper, &kathrin)
+                .unwrap()
+                .edit_script(),
+            alignment.edit_script()
+        );
+
+        let err = hamming.align(&karolin, &kathrin[1..]).err().unwrap();
+        assert_eq!(
+            err,
+            AlignError::LengthMismatch {
+                source_len: 7,
+                target_len: 6
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Cannot align sequences of different lengths (7 and 6)"
+        );
+    }
+
     #[test]
     pub fn test_levenshtein() {
         run_testcases(
@@ -290,10 +1397,109 @@ mod tests {
         );
     }
 
+    #[test]
+    pub fn test_unrestricted_levenshtein_damerau() {
+        run_testcases(
+            || UnrestrictedLevenshteinDamerau::new(1, 1, 1, 1),
+            |testcase| testcase.unrestricted_damerau_dist,
+        );
+    }
+
+    #[test]
+    pub fn test_unrestricted_levenshtein_damerau_script() {
+        use UnrestrictedLevenshteinDamerauOp::*;
+
+        let measure = UnrestrictedLevenshteinDamerau::new(1, 1, 1, 1);
+        let transpose = Transpose {
+            transpose_cost: 1,
+            insert_cost: 1,
+            delete_cost: 1,
+            equivalence: Equal,
+        };
+
+        // The transposition of 'c' and 'a' spans the insertion of 'b'.
+        let ca: Vec<char> = "xca".chars().collect();
+        let abc: Vec<char> = "xabc".chars().collect();
+        let alignment = measure.align(&ca, &abc);
+        assert_eq!(alignment.distance(), 2);
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(Match(Equal), 0, 0),
+                IndexedOperation::new(transpose, 1, 1)
+            ]
+        );
+        assert_eq!(
+            alignment
+                .edit_script_with_costs()
+                .into_iter()
+                .map(|(_, cost)| cost)
+                .collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+
+        // Deletions between transposed elements.
+        let measure = UnrestrictedLevenshteinDamerau::new(1, 3, 5, 1);
+        let source: Vec<char> = "bxya".chars().collect();
+        let target: Vec<char> = "ab".chars().collect();
+        assert_eq!(measure.distance(&source, &target), 7);
+    }
+
+    #[test]
+    pub fn test_float_costs() {
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+        let pen: Vec<char> = "pen".chars().collect();
+
+        let levenshtein =
+            Levenshtein::with_costs(OrderedFloat(1.0), OrderedFloat(1.0), OrderedFloat(0.5));
+        assert_eq!(
+            levenshtein.align(&pineapple, &pen).distance(),
+            OrderedFloat(6.5)
+        );
+        assert_eq!(levenshtein.distance(&pineapple, &pen), OrderedFloat(6.5));
+        assert!(levenshtein
+            .align_within(&pineapple, &pen, OrderedFloat(6.25))
+            .is_none());
+
+        // Uniform costs use the bit-parallel algorithm.
+        let levenshtein =
+            Levenshtein::with_costs(OrderedFloat(0.5), OrderedFloat(0.5), OrderedFloat(0.5));
+        assert_eq!(
+            levenshtein.align(&pineapple, &pen).distance(),
+            OrderedFloat(3.5)
+        );
+        assert_eq!(levenshtein.distance(&pineapple, &pen), OrderedFloat(3.5));
+    }
+
+    #[test]
+    pub fn test_integer_costs() {
+        let pineapple: Vec<char> = "pineapple".chars().collect();
+        let pen: Vec<char> = "pen".chars().collect();
+
+        let lcs = LCS::with_costs(1u16, 1);
+        assert_eq!(lcs.align(&pineapple, &pen).distance(), 8u16);
+
+        let levenshtein_damerau = LevenshteinDamerau::with_costs(
+            Saturating(100u8),
+            Saturating(100),
+            Saturating(1),
+            Saturating(1),
+        );
+        assert_eq!(
+            levenshtein_damerau.align(&pineapple, &pen).distance(),
+            Saturating(u8::max_value())
+        );
+        assert_eq!(
+            levenshtein_damerau.distance(&pineapple, &pen),
+            Saturating(u8::max_value())
+        );
+    }
+
     fn run_testcases<MF, M, DF>(measure: MF, distance: DF)
     where
         MF: Fn() -> M,
-        M: Measure<char>,
+        M: Measure<char, Cost = usize>,
+        M::Operation: crate::op::Operation<char>,
         DF: Fn(&TestCase) -> usize,
     {
         for testcase in TESTCASES.iter() {
@@ -303,7 +1509,65 @@ mod tests {
             assert_eq!(
                 distance(testcase),
                 measure.align(&source, &target).distance()
-            )
+            );
+            assert_eq!(distance(testcase), measure.distance(&source, &target));
+            assert_eq!(
+                Some(distance(testcase)),
+                measure
+                    .align_within(&source, &target, distance(testcase))
+                    .map(|alignment| alignment.distance())
+            );
+            if distance(testcase) > 0 {
+                assert!(measure
+                    .align_within(&source, &target, distance(testcase) - 1)
+                    .is_none());
+            }
         }
     }
+
+    #[test]
+    pub fn test_matrix_levenshtein() {
+        use MatrixLevenshteinOp::*;
+
+        let matrix: SubstitutionMatrix = "   a  b  c\na  3  1 -2\nb  1  2 -1\nc -2 -1  3\n"
+            .parse()
+            .unwrap();
+        let measure = MatrixLevenshtein::new(matrix, 4, 4);
+        assert!(Measure::<char>::is_symmetric(&measure));
+
+        let source = ['a', 'b', 'c'];
+        let target = ['b', 'b', 'a', 'c'];
+        let alignment = measure.align(&source, &target);
+        assert_eq!(alignment.distance(), 7);
+        assert_eq!(
+            alignment.edit_script(),
+            vec![
+                IndexedOperation::new(Substitute(measure.matrix().clone().into()), 0, 0),
+                IndexedOperation::new(Match(measure.matrix().clone().into()), 1, 1),
+                IndexedOperation::new(Insert(4), 2, 2),
+                IndexedOperation::new(Match(measure.matrix().clone().into()), 2, 3),
+            ]
+        );
+        assert_eq!(
+            format!("{:?}", alignment.edit_script()[0].operation()),
+            "Substitute"
+        );
+
+        assert_eq!(
+            measure.try_align(&['a', 'x'], &['a', 'c']).err(),
+            Some(AlignError::UnknownSymbol { symbol: 'x' })
+        );
+        assert_eq!(
+            measure.try_align(&['a'], &['y']).err(),
+            Some(AlignError::UnknownSymbol { symbol: 'y' })
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Symbol 'x' is not in the substitution matrix")]
+    pub fn test_matrix_levenshtein_unknown_symbol() {
+        let matrix: SubstitutionMatrix = "   a  b\na  3  1\nb  1  2\n".parse().unwrap();
+        let measure = MatrixLevenshtein::new(matrix, 4, 4);
+        measure.distance(&['a', 'x'], &['a', 'x']);
+    }
 }
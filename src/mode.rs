@@ -0,0 +1,59 @@
+/// Alignment mode.
+///
+/// The alignment mode determines which leading and trailing elements of
+/// the sequences can be left unaligned without any cost. By default, all
+/// elements have to be aligned (global alignment). Semi-global alignment
+/// leaves leading and/or trailing elements free, e.g. to find the best
+/// match of a short sequence in a longer sequence.
+///
+/// Free leading elements of the source (target) sequence set the first
+/// column (row) of the cost matrix to zero. Free trailing elements of the
+/// source (target) sequence make it possible to end the alignment in any
+/// cell of the last column (row) of the cost matrix.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct AlignmentMode {
+    /// Leading elements of the source sequence are free.
+    pub free_source_leading: bool,
+
+    /// Trailing elements of the source sequence are free.
+    pub free_source_trailing: bool,
+
+    /// Leading elements of the target sequence are free.
+    pub free_target_leading: bool,
+
+    /// Trailing elements of the target sequence are free.
+    pub free_target_trailing: bool,
+}
+
+impl AlignmentMode {
+    /// Global alignment, all elements have to be aligned.
+    pub fn global() -> Self {
+        AlignmentMode::default()
+    }
+
+    /// Align the source sequence with the best-matching subsequence of
+    /// the target sequence.
+    pub fn source_in_target() -> Self {
+        AlignmentMode {
+            free_target_leading: true,
+            free_target_trailing: true,
+            ..AlignmentMode::default()
+        }
+    }
+
+    /// Align the target sequence with the best-matching subsequence of
+    /// the source sequence.
+    pub fn target_in_source() -> Self {
+        AlignmentMode {
+            free_source_leading: true,
+            free_source_trailing: true,
+            ..AlignmentMode::default()
+        }
+    }
+
+    /// Check whether backtracking ends in a cell.
+    pub(crate) fn is_start_cell(self, source_idx: usize, target_idx: usize) -> bool {
+        (source_idx == 0 && (target_idx == 0 || self.free_target_leading))
+            || (target_idx == 0 && self.free_source_leading)
+    }
+}
@@ -0,0 +1,658 @@
+use std::slice;
+
+use crate::cost::Cost;
+use crate::dynprog::fill_cost_matrix;
+use crate::{Align, AlignmentMode, CostMatrix, Measure, SeqPair};
+
+/// Guide tree construction method.
+///
+/// The guide tree determines the order in which sequences and profiles
+/// are aligned in progressive multiple sequence alignment. The most
+/// similar sequences are aligned first.
+///
+/// * `Upgma`: unweighted pair group method with arithmetic mean. The
+///   distance between two clusters is the average distance between
+///   their sequences.
+/// * `NeighborJoining`: neighbor joining (Saitou and Nei, 1987), which
+///   does not assume that all sequences diverge at the same rate.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GuideTree {
+    Upgma,
+    NeighborJoining,
+}
+
+/// Trait enabling progressive multiple sequence alignment of `Measure`s.
+///
+/// Multiple sequence alignment first computes the pairwise distances of
+/// the sequences. These distances are used to build a guide tree. The
+/// sequences are then aligned progressively in the order of the guide
+/// tree, where every step aligns two profiles (alignments of one or more
+/// sequences). Once two sequences are aligned, gaps between them are
+/// never removed.
+///
+/// Profiles are aligned using the sum-of-pairs cost: the cost of aligning
+/// two columns is the sum of the costs of aligning the elements of every
+/// sequence in one column with the elements of every sequence in the
+/// other column. The cost of aligning two elements, or an element with a
+/// gap, is the measure's distance between the corresponding sequences of
+/// at most one element.
+pub trait MultipleAlign<M, T>
+where
+    M: Measure<T>,
+{
+    /// Align multiple sequences.
+    ///
+    /// Panics when none of the measure's operations can be applied to a
+    /// cell of a cost matrix.
+    fn multiple_align<'a>(
+        &self,
+        sequences: &[&'a [T]],
+        guide_tree: GuideTree,
+    ) -> MultipleAlignment<'a, T>;
+}
+
+impl<M, T> MultipleAlign<M, T> for M
+where
+    M: Measure<T>,
+{
+    fn multiple_align<'a>(
+        &self,
+        sequences: &[&'a [T]],
+        guide_tree: GuideTree,
+    ) -> MultipleAlignment<'a, T> {
+        let n_sequences = sequences.len();
+        let progressive = Progressive::new(self, sequences);
+
+        let mut distances = progressive.distances();
+        let mut profiles: Vec<Option<Profile>> = sequences
+            .iter()
+            .enumerate()
+            .map(|(idx, sequence)| Some(Profile::new(idx, sequence.len())))
+            .collect();
+        let mut active: Vec<usize> = (0..n_sequences).collect();
+
+        while active.len() > 1 {
+            let (first, second) = match guide_tree {
+                GuideTree::Upgma => closest_pair(&active, |i, j| distances[i][j]),
+                GuideTree::NeighborJoining => {
+                    let n_active = active.len() as f64;
+                    let sums: Vec<f64> = (0..n_sequences)
+                        .map(|i| active.iter().map(|&k| distances[i][k]).sum())
+                        .collect();
+                    closest_pair(&active, |i, j| {
+                        (n_active - 2.) * distances[i][j] - sums[i] - sums[j]
+                    })
+                }
+            };
+
+            let first_profile = profiles[first].take().expect("Inactive profile");
+            let second_profile = profiles[second].take().expect("Inactive profile");
+            let first_size = first_profile.rows.len() as f64;
+            let second_size = second_profile.rows.len() as f64;
+            profiles[first] = Some(progressive.align_profiles(first_profile, second_profile));
+
+            // The merged profile replaces the first profile.
+            active.retain(|&idx| idx != second);
+            for &idx in &active {
+                if idx == first {
+                    continue;
+                }
+
+                let distance = match guide_tree {
+                    GuideTree::Upgma => {
+                        (first_size * distances[first][idx] + second_size * distances[second][idx])
+                            / (first_size + second_size)
+                    }
+                    GuideTree::NeighborJoining => {
+                        (distances[first][idx] + distances[second][idx] - distances[first][second])
+                            / 2.
+                    }
+                };
+                distances[first][idx] = distance;
+                distances[idx][first] = distance;
+            }
+        }
+
+        let mut rows = vec![Vec::new(); n_sequences];
+        if let Some(profile) = active.first().and_then(|&idx| profiles[idx].take()) {
+            for (seq_idx, row) in profile.seq_indices.into_iter().zip(profile.rows) {
+                rows[seq_idx] = row;
+            }
+        }
+
+        MultipleAlignment {
+            sequences: sequences.to_vec(),
+            rows,
+        }
+    }
+}
+
+/// Find the pair of active clusters with the lowest distance.
+///
+/// Ties are broken by the order of the clusters, to give deterministic
+/// results.
+fn closest_pair<F>(active: &[usize], distance: F) -> (usize, usize)
+where
+    F: Fn(usize, usize) -> f64,
+{
+    let mut best: Option<(f64, usize, usize)> = None;
+    for (idx, &first) in active.iter().enumerate() {
+        for &second in &active[idx + 1..] {
+            let pair_distance = distance(first, second);
+            if best.map_or(true, |(best_distance, _, _)| pair_distance < best_distance) {
+                best = Some((pair_distance, first, second));
+            }
+        }
+    }
+
+    let (_, first, second) = best.expect("Fewer than two active clusters");
+    (first, second)
+}
+
+/// Alignment of one or more sequences.
+///
+/// Every row gives, for every column, the index of the element of the
+/// sequence in that column, or `None` for a gap.
+struct Profile {
+    seq_indices: Vec<usize>,
+    rows: Vec<Vec<Option<usize>>>,
+}
+
+impl Profile {
+    /// Construct the profile of a single sequence.
+    fn new(seq_idx: usize, len: usize) -> Self {
+        Profile {
+            seq_indices: vec![seq_idx],
+            rows: vec![(0..len).map(Some).collect()],
+        }
+    }
+
+    fn columns(&self) -> usize {
+        self.rows[0].len()
+    }
+}
+
+/// Indices of a source and target profile column that are aligned, where
+/// `None` is a gap column.
+type AlignedColumns = (Option<usize>, Option<usize>);
+
+/// Costs of aligning every source column with every target column
+/// (substitute), every source column with a gap column (delete), and
+/// every target column with a gap column (insert).
+struct ColumnCosts<C> {
+    substitute: CostMatrix<C>,
+    delete: Vec<C>,
+    insert: Vec<C>,
+}
+
+struct Progressive<'a, 'b, M, T>
+where
+    M: Measure<T>,
+{
+    measure: &'a M,
+    sequences: &'a [&'b [T]],
+
+    // Costs of deleting and inserting every element of every sequence.
+    delete_costs: Vec<Vec<M::Cost>>,
+    insert_costs: Vec<Vec<M::Cost>>,
+}
+
+impl<'a, 'b, M, T> Progressive<'a, 'b, M, T>
+where
+    M: Measure<T>,
+{
+    fn new(measure: &'a M, sequences: &'a [&'b [T]]) -> Self {
+        let delete_costs = sequences
+            .iter()
+            .map(|sequence| {
+                sequence
+                    .iter()
+                    .map(|elem| measure.distance(slice::from_ref(elem), &[]))
+                    .collect()
+            })
+            .collect();
+        let insert_costs = sequences
+            .iter()
+            .map(|sequence| {
+                sequence
+                    .iter()
+                    .map(|elem| measure.distance(&[], slice::from_ref(elem)))
+                    .collect()
+            })
+            .collect();
+
+        Progressive {
+            measure,
+            sequences,
+            delete_costs,
+            insert_costs,
+        }
+    }
+
+    /// Compute the pairwise distances of the sequences.
+    ///
+    /// The distance of two sequences is the average of the distances in
+    /// both directions when the measure is not symmetric.
+    fn distances(&self) -> Vec<Vec<f64>> {
+        let n_sequences = self.sequences.len();
+        let mut distances = vec![vec![0.; n_sequences]; n_sequences];
+
+        let pairs = (0..n_sequences)
+            .flat_map(|first| (first + 1..n_sequences).map(move |second| (first, second)));
+        for (first, second) in pairs {
+            let distance = if self.measure.is_symmetric() {
+                self.distance(first, second)
+            } else {
+                (self.distance(first, second) + self.distance(second, first)) / 2.
+            };
+            distances[first][second] = distance;
+            distances[second][first] = distance;
+        }
+
+        distances
+    }
+
+    fn distance(&self, source_idx: usize, target_idx: usize) -> f64 {
+        self.measure
+            .distance(self.sequences[source_idx], self.sequences[target_idx])
+            .to_f64()
+    }
+
+    /// Align two profiles, giving the merged profile.
+    fn align_profiles(&self, source: Profile, target: Profile) -> Profile {
+        let source_len = source.columns();
+        let target_len = target.columns();
+
+        let column_costs = self.column_costs(&source, &target);
+
+        // Fill the cost matrix, storing the aligned columns of the best
+        // predecessor of every cell. Substitutions are preferred over
+        // deletions over insertions.
+        let mut cost_matrix = CostMatrix::new(source_len + 1, target_len + 1);
+        let mut backtracks = vec![(None, None); (source_len + 1) * (target_len + 1)];
+        for source_idx in 0..=source_len {
+            for target_idx in 0..=target_len {
+                if source_idx == 0 && target_idx == 0 {
+                    continue;
+                }
+
+                let (cost, columns) =
+                    predecessors(&column_costs, &cost_matrix, source_idx, target_idx)
+                        .iter()
+                        .flatten()
+                        .cloned()
+                        .min_by_key(|&(cost, _)| cost)
+                        .expect("Cell without predecessors");
+                cost_matrix[(source_idx, target_idx)] = cost;
+                backtracks[source_idx * (target_len + 1) + target_idx] = columns;
+            }
+        }
+
+        let mut columns = Vec::new();
+        let (mut source_idx, mut target_idx) = (source_len, target_len);
+        while source_idx > 0 || target_idx > 0 {
+            let column = backtracks[source_idx * (target_len + 1) + target_idx];
+
+            if column.0.is_some() {
+                source_idx -= 1;
+            }
+            if column.1.is_some() {
+                target_idx -= 1;
+            }
+
+            columns.push(column);
+        }
+        columns.reverse();
+
+        let mut seq_indices = source.seq_indices;
+        seq_indices.extend(target.seq_indices);
+
+        let mut rows: Vec<_> = source
+            .rows
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .map(|&(idx, _)| idx.and_then(|idx| row[idx]))
+                    .collect()
+            })
+            .collect();
+        rows.extend(target.rows.iter().map(|row| {
+            columns
+                .iter()
+                .map(|&(_, idx)| idx.and_then(|idx| row[idx]))
+                .collect::<Vec<_>>()
+        }));
+
+        Profile { seq_indices, rows }
+    }
+
+    /// Compute the costs of aligning the columns of two profiles.
+    fn column_costs(&self, source: &Profile, target: &Profile) -> ColumnCosts<M::Cost> {
+        let source_len = source.columns();
+        let target_len = target.columns();
+
+        // Cost matrix for aligning two elements, reused for every pair of
+        // elements.
+        let mut element_matrix = CostMatrix::new(2, 2);
+
+        let mut substitute = CostMatrix::new(source_len, target_len);
+        for source_idx in 0..source_len {
+            for target_idx in 0..target_len {
+                substitute[(source_idx, target_idx)] = self.column_cost(
+                    source,
+                    Some(source_idx),
+                    target,
+                    Some(target_idx),
+                    &mut element_matrix,
+                );
+            }
+        }
+
+        ColumnCosts {
+            substitute,
+            delete: (0..source_len)
+                .map(|idx| self.column_cost(source, Some(idx), target, None, &mut element_matrix))
+                .collect(),
+            insert: (0..target_len)
+                .map(|idx| self.column_cost(source, None, target, Some(idx), &mut element_matrix))
+                .collect(),
+        }
+    }
+
+    /// Get the sum-of-pairs cost of aligning a source column with a target
+    /// column. A column index of `None` is a column of gaps.
+    fn column_cost(
+        &self,
+        source: &Profile,
+        source_column: Option<usize>,
+        target: &Profile,
+        target_column: Option<usize>,
+        element_matrix: &mut CostMatrix<M::Cost>,
+    ) -> M::Cost {
+        let mut cost = M::Cost::zero();
+
+        for (&source_seq, source_row) in source.seq_indices.iter().zip(&source.rows) {
+            let source_elem = source_column
+                .and_then(|idx| source_row[idx])
+                .map(|idx| (source_seq, idx));
+
+            for (&target_seq, target_row) in target.seq_indices.iter().zip(&target.rows) {
+                let target_elem = target_column
+                    .and_then(|idx| target_row[idx])
+                    .map(|idx| (target_seq, idx));
+
+                cost = cost.saturating_add(self.element_cost(
+                    source_elem,
+                    target_elem,
+                    element_matrix,
+                ));
+            }
+        }
+
+        cost
+    }
+
+    /// Get the cost of aligning two elements, where an element is given
+    /// by its sequence and element index and `None` is a gap.
+    fn element_cost(
+        &self,
+        source: Option<(usize, usize)>,
+        target: Option<(usize, usize)>,
+        element_matrix: &mut CostMatrix<M::Cost>,
+    ) -> M::Cost {
+        match (source, target) {
+            (None, None) => M::Cost::zero(),
+            (Some((seq, idx)), None) => self.delete_costs[seq][idx],
+            (None, Some((seq, idx))) => self.insert_costs[seq][idx],
+            (Some((source_seq, source_idx)), Some((target_seq, target_idx))) => {
+                let pair = SeqPair {
+                    source: slice::from_ref(&self.sequences[source_seq][source_idx]),
+                    target: slice::from_ref(&self.sequences[target_seq][target_idx]),
+                };
+                fill_cost_matrix(self.measure, &pair, element_matrix, AlignmentMode::global())
+                    .unwrap_or_else(|err| panic!("{}", err));
+
+                element_matrix[(1, 1)]
+            }
+        }
+    }
+}
+
+/// Get the costs of reaching a cell by substitution, deletion, and
+/// insertion, together with the columns that were aligned.
+fn predecessors<C>(
+    column_costs: &ColumnCosts<C>,
+    cost_matrix: &CostMatrix<C>,
+    source_idx: usize,
+    target_idx: usize,
+) -> [Option<(C, AlignedColumns)>; 3]
+where
+    C: Cost,
+{
+    let substitute = if source_idx > 0 && target_idx > 0 {
+        Some((
+            cost_matrix[(source_idx - 1, target_idx - 1)]
+                .saturating_add(column_costs.substitute[(source_idx - 1, target_idx - 1)]),
+            (Some(source_idx - 1), Some(target_idx - 1)),
+        ))
+    } else {
+        None
+    };
+
+    let delete = if source_idx > 0 {
+        Some((
+            cost_matrix[(source_idx - 1, target_idx)]
+                .saturating_add(column_costs.delete[source_idx - 1]),
+            (Some(source_idx - 1), None),
+        ))
+    } else {
+        None
+    };
+
+    let insert = if target_idx > 0 {
+        Some((
+            cost_matrix[(source_idx, target_idx - 1)]
+                .saturating_add(column_costs.insert[target_idx - 1]),
+            (None, Some(target_idx - 1)),
+        ))
+    } else {
+        None
+    };
+
+    [substitute, delete, insert]
+}
+
+/// Multiple sequence alignment.
+///
+/// The alignment consists of a row for every sequence, in the order in
+/// which the sequences were given. All rows have the same number of
+/// columns. A column gives the elements of the sequences that were
+/// aligned, where `None` marks a gap.
+///
+/// ```
+/// use seqalign::{GuideTree, MultipleAlign};
+/// use seqalign::measures::Levenshtein;
+///
+/// let variants: Vec<Vec<char>> = ["colour", "color", "coulor", "colour"]
+///     .iter()
+///     .map(|variant| variant.chars().collect())
+///     .collect();
+/// let sequences: Vec<&[char]> = variants.iter().map(Vec::as_slice).collect();
+///
+/// let measure = Levenshtein::new(1, 1, 1);
+/// let alignment = measure.multiple_align(&sequences, GuideTree::Upgma);
+///
+/// assert_eq!(alignment.rows(), 4);
+/// assert_eq!(alignment.padded_row(1, &'-').into_iter().collect::<String>(), "co-lo-r");
+/// assert_eq!(alignment.consensus().into_iter().collect::<String>(), "colour");
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultipleAlignment<'a, T> {
+    sequences: Vec<&'a [T]>,
+    rows: Vec<Vec<Option<usize>>>,
+}
+
+impl<'a, T> MultipleAlignment<'a, T> {
+    /// Get the elements of a column.
+    ///
+    /// The column contains an element or gap (`None`) for every sequence.
+    pub fn column(&self, column: usize) -> Vec<Option<&'a T>> {
+        (0..self.rows())
+            .map(|row| self.element(row, column))
+            .collect()
+    }
+
+    /// Get the number of columns.
+    pub fn columns(&self) -> usize {
+        self.rows.first().map(Vec::len).unwrap_or(0)
+    }
+
+    /// Get the consensus sequence.
+    ///
+    /// The consensus sequence contains the most frequent element of every
+    /// column. Columns where gaps are more frequent than the most frequent
+    /// element are skipped. Ties are broken by the order of the sequences.
+    pub fn consensus(&self) -> Vec<T>
+    where
+        T: Clone + Eq,
+    {
+        let mut consensus = Vec::new();
+
+        for column in (0..self.columns()).map(|idx| self.column(idx)) {
+            let n_gaps = column.iter().filter(|elem| elem.is_none()).count();
+
+            let mut best: Option<(&T, usize)> = None;
+            for elem in column.iter().flatten() {
+                let count = column.iter().filter(|other| *other == &Some(*elem)).count();
+                if best.map_or(true, |(_, best_count)| count > best_count) {
+                    best = Some((elem, count));
+                }
+            }
+
+            if let Some((elem, count)) = best {
+                if count >= n_gaps {
+                    consensus.push(elem.clone());
+                }
+            }
+        }
+
+        consensus
+    }
+
+    /// Get the elements of a row.
+    ///
+    /// The row contains an element or gap (`None`) for every column.
+    pub fn row(&self, row: usize) -> Vec<Option<&'a T>> {
+        (0..self.columns())
+            .map(|column| self.element(row, column))
+            .collect()
+    }
+
+    /// Get the number of rows.
+    ///
+    /// This is the number of aligned sequences.
+    pub fn rows(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Get the elements of a row, where gaps are marked with `gap`.
+    pub fn padded_row(&self, row: usize, gap: &T) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.row(row)
+            .into_iter()
+            .map(|elem| elem.unwrap_or(gap).clone())
+            .collect()
+    }
+
+    fn element(&self, row: usize, column: usize) -> Option<&'a T> {
+        let sequence = self.sequences[row];
+        self.rows[row][column].map(|idx| &sequence[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::measures::{Levenshtein, LCS};
+
+    use super::GuideTree::*;
+    use super::{MultipleAlign, MultipleAlignment};
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    fn padded_rows(alignment: &MultipleAlignment<char>) -> Vec<String> {
+        (0..alignment.rows())
+            .map(|row| alignment.padded_row(row, &'-').into_iter().collect())
+            .collect()
+    }
+
+    #[test]
+    fn multiple_align_test() {
+        let variants: Vec<_> = ["seqalign", "seqalgn", "sequalign", "seqaligm", "seqalign"]
+            .iter()
+            .map(|variant| chars(variant))
+            .collect();
+        let sequences: Vec<&[char]> = variants.iter().map(Vec::as_slice).collect();
+
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        for &guide_tree in &[Upgma, NeighborJoining] {
+            let alignment = levenshtein.multiple_align(&sequences, guide_tree);
+            assert_eq!(
+                padded_rows(&alignment),
+                vec![
+                    "seq-align",
+                    "seq-al-gn",
+                    "sequalign",
+                    "seq-aligm",
+                    "seq-align"
+                ]
+            );
+            assert_eq!(alignment.consensus(), chars("seqalign"));
+            assert_eq!(
+                alignment.column(3),
+                vec![None, None, Some(&'u'), None, None]
+            );
+        }
+    }
+
+    #[test]
+    fn multiple_align_lcs_test() {
+        let variants: Vec<_> = ["abcd", "abd", "acd", "bcd"]
+            .iter()
+            .map(|variant| chars(variant))
+            .collect();
+        let sequences: Vec<&[char]> = variants.iter().map(Vec::as_slice).collect();
+
+        let alignment = LCS::new(1, 1).multiple_align(&sequences, Upgma);
+        assert_eq!(
+            padded_rows(&alignment),
+            vec!["abcd", "ab-d", "a-cd", "-bcd"]
+        );
+        assert_eq!(alignment.consensus(), chars("abcd"));
+    }
+
+    #[test]
+    fn multiple_align_trivial_test() {
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        let none: &[&[char]] = &[];
+        let alignment = levenshtein.multiple_align(none, Upgma);
+        assert_eq!(alignment.rows(), 0);
+        assert_eq!(alignment.columns(), 0);
+        assert!(alignment.consensus().is_empty());
+
+        let apple = chars("apple");
+        let alignment = levenshtein.multiple_align(&[&apple], NeighborJoining);
+        assert_eq!(padded_rows(&alignment), vec!["apple"]);
+        assert_eq!(alignment.consensus(), apple);
+
+        let empty: &[char] = &[];
+        let alignment = levenshtein.multiple_align(&[empty, &apple, empty], Upgma);
+        assert_eq!(padded_rows(&alignment), vec!["-----", "apple", "-----"]);
+        assert_eq!(alignment.consensus(), Vec::<char>::new());
+    }
+}
@@ -0,0 +1,211 @@
+//! Bit-parallel Levenshtein distance.
+//!
+//! This module implements Myers' bit-vector algorithm for computing the
+//! unit-cost Levenshtein distance, using Hyyrö's formulation and blocks
+//! of machine words for patterns that do not fit in a single word.
+
+use crate::equivalence::Equivalence;
+
+const WORD_SIZE: usize = 64;
+
+const HIGH_BIT: u64 = 1 << (WORD_SIZE - 1);
+
+/// Match vectors of a pattern.
+///
+/// The match vector of a text symbol is split into blocks of words, where
+/// bit *i* is set if the symbol is equivalent to position *i* of the
+/// pattern. Equivalences are not necessarily transitive, so the match
+/// vector is computed for every text symbol, rather than once for every
+/// distinct symbol of the pattern.
+struct PatternEq<'a, T, E> {
+    pattern: &'a [T],
+    equivalence: &'a E,
+    swapped: bool,
+    eq: Vec<u64>,
+}
+
+impl<'a, T, E> PatternEq<'a, T, E>
+where
+    E: Equivalence<T>,
+{
+    /// Construct the match vectors of a pattern. If `swapped` is true,
+    /// the pattern is the target sequence, and it is passed to the
+    /// equivalence as the second element.
+    fn new(pattern: &'a [T], equivalence: &'a E, swapped: bool, n_blocks: usize) -> Self {
+        PatternEq {
+            pattern,
+            equivalence,
+            swapped,
+            eq: vec![0; n_blocks],
+        }
+    }
+
+    fn eq(&mut self, symbol: &T) -> &[u64] {
+        for block in &mut self.eq {
+            *block = 0;
+        }
+
+        for (idx, pattern_symbol) in self.pattern.iter().enumerate() {
+            let equivalent = if self.swapped {
+                self.equivalence.equivalent(symbol, pattern_symbol)
+            } else {
+                self.equivalence.equivalent(pattern_symbol, symbol)
+            };
+
+            if equivalent {
+                self.eq[idx / WORD_SIZE] |= 1 << (idx % WORD_SIZE);
+            }
+        }
+
+        &self.eq
+    }
+}
+
+/// Compute the unit-cost Levenshtein distance of two sequences.
+pub(crate) fn levenshtein_distance<T, E>(source: &[T], target: &[T], equivalence: &E) -> usize
+where
+    E: Equivalence<T>,
+{
+    // The distance is symmetric, use the shortest sequence as the pattern
+    // to minimize the number of blocks.
+    let (pattern, text, swapped) = if source.len() <= target.len() {
+        (source, target, false)
+    } else {
+        (target, source, true)
+    };
+
+    if pattern.is_empty() {
+        return text.len();
+    }
+
+    let n_blocks = (pattern.len() + WORD_SIZE - 1) / WORD_SIZE;
+    let last_bit = 1 << ((pattern.len() - 1) % WORD_SIZE);
+
+    let mut peq = PatternEq::new(pattern, equivalence, swapped, n_blocks);
+    // Vertical deltas, initially +1 since D[i][0] = i.
+    let mut pv = vec![!0u64; n_blocks];
+    let mut mv = vec![0u64; n_blocks];
+
+    let mut score = pattern.len();
+    for symbol in text {
+        let eq = peq.eq(symbol);
+
+        // Horizontal delta in the first row is +1, since D[0][j] = j.
+        let mut h = 1;
+        for block in 0..n_blocks {
+            let out_mask = if block == n_blocks - 1 {
+                last_bit
+            } else {
+                HIGH_BIT
+            };
+
+            h = advance_block(&mut pv[block], &mut mv[block], eq[block], h, out_mask);
+        }
+
+        score = (score as isize + h) as usize;
+    }
+
+    score
+}
+
+/// Advance a block by one text symbol.
+///
+/// `h_in` is the horizontal delta that enters the block from the block
+/// above. Returns the horizontal delta of the row given by `out_mask`.
+fn advance_block(pv: &mut u64, mv: &mut u64, eq: u64, h_in: isize, out_mask: u64) -> isize {
+    let h_in_neg = (h_in < 0) as u64;
+    let h_in_pos = (h_in > 0) as u64;
+
+    let xv = eq | *mv;
+    let eq = eq | h_in_neg;
+    let xh = (((eq & *pv).wrapping_add(*pv)) ^ *pv) | eq;
+
+    let mut ph = *mv | !(xh | *pv);
+    let mut mh = *pv & xh;
+
+    let h_out = if ph & out_mask != 0 {
+        1
+    } else if mh & out_mask != 0 {
+        -1
+    } else {
+        0
+    };
+
+    ph = (ph << 1) | h_in_pos;
+    mh = (mh << 1) | h_in_neg;
+
+    *pv = mh | !(xv | ph);
+    *mv = ph & xv;
+
+    h_out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::equivalence::{Equal, EquivalenceFn};
+    use crate::measures::Levenshtein;
+    use crate::Align;
+
+    use super::levenshtein_distance;
+
+    fn check_distance(source: &[char], target: &[char]) {
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        assert_eq!(
+            levenshtein.align(source, target).distance(),
+            levenshtein_distance(source, target, &Equal)
+        );
+    }
+
+    #[test]
+    fn levenshtein_distance_test() {
+        let pairs = [
+            ("", ""),
+            ("pineapple", ""),
+            ("", "pineapple"),
+            ("pineapple", "pen"),
+            ("pen", "pineapple"),
+            ("pineapple", "applet"),
+            ("applet", "pen"),
+            ("tpyo", "typo"),
+        ];
+
+        for &(source, target) in &pairs {
+            let source: Vec<char> = source.chars().collect();
+            let target: Vec<char> = target.chars().collect();
+            check_distance(&source, &target);
+        }
+    }
+
+    #[test]
+    fn levenshtein_distance_blocks_test() {
+        let source: Vec<char> = "the quick brown fox jumps over the lazy dog "
+            .repeat(5)
+            .chars()
+            .collect();
+        let target: Vec<char> = "a quick brown dog jumped over the lazy fox "
+            .repeat(4)
+            .chars()
+            .collect();
+
+        for &len in &[63, 64, 65, 127, 128, 129, 200] {
+            check_distance(&source[..len], &target);
+            check_distance(&source[..len], &target[..len.min(target.len())]);
+            check_distance(&source[1..len], &source[..len]);
+        }
+    }
+
+    #[test]
+    fn levenshtein_distance_equivalence_test() {
+        let case_insensitive = EquivalenceFn::new(|a: &char, b: &char| a.eq_ignore_ascii_case(b));
+        let levenshtein = Levenshtein::new(1, 1, 1).with_equivalence(case_insensitive.clone());
+
+        for &(source, target) in &[("PineApple", "pen"), ("pen", "PineApple")] {
+            let source: Vec<char> = source.chars().collect();
+            let target: Vec<char> = target.chars().collect();
+            assert_eq!(
+                levenshtein_distance(&source, &target, &case_insensitive),
+                levenshtein.align(&source, &target).distance()
+            );
+        }
+    }
+}
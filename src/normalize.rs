@@ -0,0 +1,344 @@
+use crate::cost::Cost;
+use crate::op::Operation;
+use crate::{AlignError, Alignment, CostMatrix, Measure};
+
+/// Edit distance normalization.
+///
+/// Raw edit distances cannot be compared across pairs of sequences of
+/// different lengths. A normalization divides the edit distance by a
+/// length:
+///
+/// * `MaxLength`: the length of the longest sequence;
+/// * `AlignmentLength`: the number of operations in the edit script;
+/// * `MarzalVidal`: the number of operations of the edit path that
+///   minimizes the ratio of its cost and its number of operations
+///   (Marzal and Vidal, 1993).
+///
+/// Marzal-Vidal normalization is not the same as dividing the edit
+/// distance by the alignment length, since a path with a higher cost can
+/// have a lower cost per operation. It is computed in *O(mn(m + n))*
+/// time for sequences of lengths *m* and *n*.
+///
+/// When all operations cost at most *1*, normalized distances are in
+/// *[0, 1]*.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Normalization {
+    MaxLength,
+    AlignmentLength,
+    MarzalVidal,
+}
+
+impl<'a, M, S, U> Alignment<'a, M, S, U>
+where
+    M: Measure<S, U>,
+{
+    /// Get the normalized edit distance.
+    ///
+    /// The normalized distance of two empty sequences is zero.
+    ///
+    /// ```
+    /// use seqalign::{Align, Normalization};
+    /// use seqalign::measures::Levenshtein;
+    ///
+    /// let measure = Levenshtein::new(1, 1, 1);
+    /// let source = &['a', 'b', 'c', 'd'];
+    /// let target = &['a', 'c', 'd'];
+    /// let alignment = measure.align(source, target);
+    ///
+    /// assert_eq!(alignment.normalized_distance(Normalization::MaxLength), 0.25);
+    /// assert_eq!(alignment.similarity(Normalization::MaxLength), 0.75);
+    /// ```
+    ///
+    /// Panics when the normalization requires an edit path and there is no
+    /// edit path from a start cell to an end cell of the cost matrix. Use
+    /// `try_normalized_distance` to get an error instead.
+    pub fn normalized_distance(&self, normalization: Normalization) -> f64 {
+        self.try_normalized_distance(normalization)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Get the normalized edit distance.
+    ///
+    /// This method is the same as `normalized_distance`, but returns an
+    /// error when the normalization requires an edit path and there is no
+    /// edit path from a start cell to an end cell of the cost matrix.
+    pub fn try_normalized_distance(&self, normalization: Normalization) -> Result<f64, AlignError> {
+        match normalization {
+            Normalization::MaxLength => {
+                let max_len = self.pair.source.len().max(self.pair.target.len());
+                Ok(ratio(self.distance(), max_len))
+            }
+            Normalization::AlignmentLength => {
+                Ok(ratio(self.distance(), self.try_edit_script()?.len()))
+            }
+            Normalization::MarzalVidal => self.marzal_vidal_distance(),
+        }
+    }
+
+    /// Get the similarity of the sequences.
+    ///
+    /// The similarity is one minus the normalized edit distance, clamped
+    /// to *[0, 1]*. The similarity of two empty sequences is one.
+    ///
+    /// Panics under the same conditions as `normalized_distance`. Use
+    /// `try_similarity` to get an error instead.
+    pub fn similarity(&self, normalization: Normalization) -> f64 {
+        self.try_similarity(normalization)
+            .unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Get the similarity of the sequences.
+    ///
+    /// This method is the same as `similarity`, but returns an error
+    /// under the same conditions as `try_normalized_distance`.
+    pub fn try_similarity(&self, normalization: Normalization) -> Result<f64, AlignError> {
+        Ok((1. - self.try_normalized_distance(normalization)?)
+            .max(0.)
+            .min(1.))
+    }
+
+    fn marzal_vidal_distance(&self) -> Result<f64, AlignError> {
+        let (rows, columns) = self.cost_matrix.shape();
+        let pair = &self.pair;
+
+        // Predecessors of every cell with the costs of the operations
+        // themselves, found by applying the operations to a cost matrix of
+        // zeros.
+        let zeros = CostMatrix::with_stored_rows(rows, columns, 1);
+        let mut predecessors = Vec::with_capacity(rows * columns);
+        for source_idx in 0..rows {
+            for target_idx in 0..columns {
+                let cell_predecessors: Vec<_> = self
+                    .measure
+                    .operations()
+                    .iter()
+                    .filter_map(|op| {
+                        let from = op.backtrack(pair, source_idx, target_idx)?;
+                        let cost = op.cost(pair, &zeros, source_idx, target_idx)?;
+                        Some((from, cost))
+                    })
+                    .collect();
+                predecessors.push(cell_predecessors);
+            }
+        }
+
+        // Lowest cost of reaching every cell with exactly n_ops
+        // operations, starting with zero operations in the start cells.
+        let mut costs: Vec<Option<M::Cost>> = (0..rows * columns)
+            .map(|idx| {
+                if self.mode.is_start_cell(idx / columns, idx % columns) {
+                    Some(M::Cost::zero())
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let mut best = self.min_end_cost(&costs).map(|_| 0.);
+
+        // Every operation consumes at least one element, so a path has at
+        // most as many operations as there are elements.
+        for n_ops in 1..rows + columns - 1 {
+            costs = predecessors
+                .iter()
+                .map(|cell_predecessors| {
+                    cell_predecessors
+                        .iter()
+                        .filter_map(|&((from_source_idx, from_target_idx), op_cost)| {
+                            costs[from_source_idx * columns + from_target_idx]
+                                .map(|cost| cost.saturating_add(op_cost))
+                        })
+                        .min()
+                })
+                .collect();
+
+            if let Some(cost) = self.min_end_cost(&costs) {
+                let ratio = ratio(cost, n_ops);
+                best = Some(best.map_or(ratio, |best: f64| best.min(ratio)));
+            }
+        }
+
+        best.ok_or(AlignError::CannotBacktrack {
+            source_idx: rows - 1,
+            target_idx: columns - 1,
+        })
+    }
+
+    /// Get the lowest cost of the cells where an alignment can end.
+    fn min_end_cost(&self, costs: &[Option<M::Cost>]) -> Option<M::Cost> {
+        let (rows, columns) = self.cost_matrix.shape();
+        let (source_len, target_len) = (rows - 1, columns - 1);
+
+        let mut end_costs = vec![costs[source_len * columns + target_len]];
+        if self.mode.free_source_trailing {
+            end_costs
+                .extend((0..source_len).map(|source_idx| costs[source_idx * columns + target_len]));
+        }
+        if self.mode.free_target_trailing {
+            end_costs
+                .extend((0..target_len).map(|target_idx| costs[source_len * columns + target_idx]));
+        }
+
+        end_costs.into_iter().flatten().min()
+    }
+}
+
+fn ratio<C>(cost: C, len: usize) -> f64
+where
+    C: Cost,
+{
+    if len == 0 {
+        0.
+    } else {
+        cost.to_f64() / len as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::measures::{Levenshtein, LevenshteinDamerau};
+    use crate::op::Operation;
+    use crate::{Align, AlignError, AlignmentMode, CostMatrix, Measure, SeqPair};
+
+    use super::Normalization::*;
+
+    /// Operation that can be applied everywhere, but cannot be backtracked.
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    struct Teleport;
+
+    impl<T> Operation<T> for Teleport {
+        fn backtrack(
+            &self,
+            _seq_pair: &SeqPair<T>,
+            _source_idx: usize,
+            _target_idx: usize,
+        ) -> Option<(usize, usize)> {
+            None
+        }
+
+        fn cost(
+            &self,
+            _seq_pair: &SeqPair<T>,
+            _cost_matrix: &CostMatrix,
+            _source_idx: usize,
+            _target_idx: usize,
+        ) -> Option<usize> {
+            Some(0)
+        }
+    }
+
+    struct TeleportOnly([Teleport; 1]);
+
+    impl<T> Measure<T> for TeleportOnly {
+        type Cost = usize;
+
+        type Operation = Teleport;
+
+        fn operations(&self) -> &[Self::Operation] {
+            &self.0
+        }
+    }
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+
+    #[test]
+    fn normalized_distance_test() {
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        let pineapple = chars("pineapple");
+        let pen = chars("pen");
+        let alignment = levenshtein.align(&pineapple, &pen);
+        assert_eq!(alignment.normalized_distance(MaxLength), 7. / 9.);
+        assert_eq!(alignment.normalized_distance(AlignmentLength), 7. / 9.);
+        assert_eq!(alignment.similarity(MaxLength), 1. - 7. / 9.);
+
+        // A transposition is a single operation, so the edit script is
+        // shorter than the sequences.
+        let damerau = LevenshteinDamerau::new(1, 1, 1, 1);
+        let tpyo = chars("tpyo");
+        let typo = chars("typo");
+        let alignment = damerau.align(&tpyo, &typo);
+        assert_eq!(alignment.normalized_distance(MaxLength), 0.25);
+        assert_eq!(alignment.normalized_distance(AlignmentLength), 1. / 3.);
+        assert_eq!(alignment.similarity(AlignmentLength), 1. - 1. / 3.);
+
+        let empty: &[char] = &[];
+        let empty = levenshtein.align(empty, empty);
+        for &normalization in &[MaxLength, AlignmentLength, MarzalVidal] {
+            assert_eq!(empty.normalized_distance(normalization), 0.);
+            assert_eq!(empty.similarity(normalization), 1.);
+        }
+    }
+
+    #[test]
+    fn marzal_vidal_test() {
+        // Example from Marzal and Vidal (1993): with an insertion and
+        // deletion cost of 2 and a substitution cost of 3, substituting
+        // is cheaper, but deleting and inserting gives a lower cost per
+        // operation.
+        let levenshtein = Levenshtein::new(2, 2, 3);
+
+        let source = chars("abbb");
+        let target = chars("aaab");
+        let alignment = levenshtein.align(&source, &target);
+        assert_eq!(alignment.distance(), 6);
+        assert_eq!(alignment.normalized_distance(AlignmentLength), 1.5);
+        assert_eq!(alignment.normalized_distance(MarzalVidal), 8. / 6.);
+
+        // With unit costs, matching elements can only lower the ratio.
+        let levenshtein = Levenshtein::new(1, 1, 1);
+        let pineapple = chars("pineapple");
+        let pen = chars("pen");
+        let alignment = levenshtein.align(&pineapple, &pen);
+        assert_eq!(alignment.normalized_distance(MarzalVidal), 7. / 9.);
+        assert_eq!(alignment.similarity(MarzalVidal), 1. - 7. / 9.);
+
+        let source = chars("abc");
+        let target = chars("xyz");
+        let alignment = levenshtein.align(&source, &target);
+        assert_eq!(alignment.normalized_distance(MarzalVidal), 1.);
+        assert_eq!(alignment.similarity(MarzalVidal), 0.);
+    }
+
+    #[test]
+    fn try_normalized_distance_test() {
+        let pineapple = chars("pineapple");
+        let pen = chars("pen");
+
+        let teleport_only = TeleportOnly([Teleport]);
+        let alignment = teleport_only.align(&pineapple, &pen);
+        let no_path = Err(AlignError::CannotBacktrack {
+            source_idx: 9,
+            target_idx: 3,
+        });
+        assert_eq!(alignment.try_normalized_distance(MaxLength), Ok(0.));
+        assert_eq!(alignment.try_normalized_distance(AlignmentLength), no_path);
+        assert_eq!(alignment.try_normalized_distance(MarzalVidal), no_path);
+        assert_eq!(alignment.try_similarity(MarzalVidal), no_path);
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot backtrack from cell (9, 3) to cell (0, 0)")]
+    fn marzal_vidal_panic_test() {
+        let pineapple = chars("pineapple");
+        let pen = chars("pen");
+        TeleportOnly([Teleport])
+            .align(&pineapple, &pen)
+            .normalized_distance(MarzalVidal);
+    }
+
+    #[test]
+    fn marzal_vidal_mode_test() {
+        let levenshtein = Levenshtein::new(1, 1, 1);
+
+        let source = chars("pen");
+        let target = chars("xxpenxx");
+        let alignment =
+            levenshtein.align_with_mode(&source, &target, AlignmentMode::source_in_target());
+        assert_eq!(alignment.distance(), 0);
+        assert_eq!(alignment.normalized_distance(MarzalVidal), 0.);
+        assert_eq!(alignment.similarity(MarzalVidal), 1.);
+    }
+}
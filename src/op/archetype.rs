@@ -4,17 +4,25 @@
 //! not meant to be used directly, but can be used in the implementation
 //! of new measures.
 
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::cost::{Cost, ElementCosts};
+use crate::equivalence::{Equal, Equivalence};
 use crate::op::Operation;
-use crate::SeqPair;
+use crate::{CostMatrix, SeqPair};
 
 /// Delete operation with associated cost.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Delete(pub usize);
+pub struct Delete<C = usize>(pub C);
 
-impl<T> Operation<T> for Delete {
+impl<S, U, C> Operation<S, C, U> for Delete<C>
+where
+    C: Cost,
+{
     fn backtrack(
         &self,
-        _seq_pair: &SeqPair<T>,
+        _seq_pair: &SeqPair<S, U>,
         source_idx: usize,
         target_idx: usize,
     ) -> Option<(usize, usize)> {
@@ -27,29 +35,29 @@ impl<T> Operation<T> for Delete {
 
     fn cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq,
-    {
+    ) -> Option<C> {
         let (from_source_idx, from_target_idx) =
             self.backtrack(seq_pair, source_idx, target_idx)?;
-        let orig_cost = cost_matrix[from_source_idx][from_target_idx];
-        Some(orig_cost + self.0)
+        let orig_cost = cost_matrix[(from_source_idx, from_target_idx)];
+        Some(orig_cost.saturating_add(self.0))
     }
 }
 
 /// Insert operation with associated cost.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Insert(pub usize);
+pub struct Insert<C = usize>(pub C);
 
-impl<T> Operation<T> for Insert {
+impl<S, U, C> Operation<S, C, U> for Insert<C>
+where
+    C: Cost,
+{
     fn backtrack(
         &self,
-        _seq_pair: &SeqPair<T>,
+        _seq_pair: &SeqPair<S, U>,
         source_idx: usize,
         target_idx: usize,
     ) -> Option<(usize, usize)> {
@@ -62,29 +70,62 @@ impl<T> Operation<T> for Insert {
 
     fn cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq,
-    {
+    ) -> Option<C> {
         let (from_source_idx, from_target_idx) =
             self.backtrack(seq_pair, source_idx, target_idx)?;
-        let orig_cost = cost_matrix[from_source_idx][from_target_idx];
-        Some(orig_cost + self.0)
+        let orig_cost = cost_matrix[(from_source_idx, from_target_idx)];
+        Some(orig_cost.saturating_add(self.0))
     }
 }
 
 /// Match operation.
+///
+/// Elements are matched when they are equal according to `Eq`. Use
+/// `MatchWith` to match elements using another equivalence.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Match;
 
-impl<T> Operation<T> for Match {
+impl<T, C> Operation<T, C> for Match
+where
+    T: Eq,
+    C: Cost,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<T>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        Operation::<T, C>::backtrack(&MatchWith(Equal), seq_pair, source_idx, target_idx)
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<T>,
+        cost_matrix: &CostMatrix<C>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<C> {
+        MatchWith(Equal).cost(seq_pair, cost_matrix, source_idx, target_idx)
+    }
+}
+
+/// Match operation with an element equivalence.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct MatchWith<E>(pub E);
+
+impl<S, U, C, E> Operation<S, C, U> for MatchWith<E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<S, U> + Eq + Hash,
+{
     fn backtrack(
         &self,
-        _seq_pair: &SeqPair<T>,
+        _seq_pair: &SeqPair<S, U>,
         source_idx: usize,
         target_idx: usize,
     ) -> Option<(usize, usize)> {
@@ -97,19 +138,19 @@ impl<T> Operation<T> for Match {
 
     fn cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq,
-    {
+    ) -> Option<C> {
         let (from_source_idx, from_target_idx) =
-            self.backtrack(seq_pair, source_idx, target_idx)?;
+            Operation::<S, C, U>::backtrack(self, seq_pair, source_idx, target_idx)?;
 
-        if seq_pair.source[from_source_idx] == seq_pair.target[from_target_idx] {
-            Some(cost_matrix[from_source_idx][from_target_idx])
+        if self.0.equivalent(
+            &seq_pair.source[from_source_idx],
+            &seq_pair.target[from_target_idx],
+        ) {
+            Some(cost_matrix[(from_source_idx, from_target_idx)])
         } else {
             None
         }
@@ -118,12 +159,15 @@ impl<T> Operation<T> for Match {
 
 /// Substitute operation with associated cost.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Substitute(pub usize);
+pub struct Substitute<C = usize>(pub C);
 
-impl<T> Operation<T> for Substitute {
+impl<S, U, C> Operation<S, C, U> for Substitute<C>
+where
+    C: Cost,
+{
     fn backtrack(
         &self,
-        _seq_pair: &SeqPair<T>,
+        _seq_pair: &SeqPair<S, U>,
         source_idx: usize,
         target_idx: usize,
     ) -> Option<(usize, usize)> {
@@ -136,29 +180,62 @@ impl<T> Operation<T> for Substitute {
 
     fn cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq,
-    {
+    ) -> Option<C> {
         let (from_source_idx, from_target_idx) =
             self.backtrack(seq_pair, source_idx, target_idx)?;
-        let orig_cost = cost_matrix[from_source_idx][from_target_idx];
-        Some(orig_cost + self.0)
+        let orig_cost = cost_matrix[(from_source_idx, from_target_idx)];
+        Some(orig_cost.saturating_add(self.0))
     }
 }
 
 /// Transpose operation with associated cost.
+///
+/// Elements are compared using `Eq`. Use `TransposeWith` to compare
+/// elements using another equivalence.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Transpose<C = usize>(pub C);
+
+impl<T, C> Operation<T, C> for Transpose<C>
+where
+    T: Eq,
+    C: Cost,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<T>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        TransposeWith(self.0, Equal).backtrack(seq_pair, source_idx, target_idx)
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<T>,
+        cost_matrix: &CostMatrix<C>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<C> {
+        TransposeWith(self.0, Equal).cost(seq_pair, cost_matrix, source_idx, target_idx)
+    }
+}
+
+/// Transpose operation with associated cost and an element equivalence.
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Transpose(pub usize);
+pub struct TransposeWith<C, E>(pub C, pub E);
 
-impl<T> Operation<T> for Transpose {
+impl<S, U, C, E> Operation<S, C, U> for TransposeWith<C, E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<S, U> + Eq + Hash,
+{
     fn backtrack(
         &self,
-        _seq_pair: &SeqPair<T>,
+        _seq_pair: &SeqPair<S, U>,
         source_idx: usize,
         target_idx: usize,
     ) -> Option<(usize, usize)> {
@@ -171,23 +248,267 @@ impl<T> Operation<T> for Transpose {
 
     fn cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq,
-    {
+    ) -> Option<C> {
         let (from_source_idx, from_target_idx) =
             self.backtrack(seq_pair, source_idx, target_idx)?;
 
-        if seq_pair.source[from_source_idx] == seq_pair.target[from_target_idx + 1]
-            && seq_pair.source[from_source_idx + 1] == seq_pair.target[from_target_idx]
-        {
-            Some(cost_matrix[from_source_idx][from_target_idx] + self.0)
+        let source = &seq_pair.source[from_source_idx..from_source_idx + 2];
+        let target = &seq_pair.target[from_target_idx..from_target_idx + 2];
+        if self.1.equivalent(&source[0], &target[1]) && self.1.equivalent(&source[1], &target[0]) {
+            Some(cost_matrix[(from_source_idx, from_target_idx)].saturating_add(self.0))
         } else {
             None
         }
     }
 }
+
+/// Transpose operation that can span intervening edits.
+///
+/// This operation transposes two elements *a* and *b*, where the
+/// elements between *b* and *a* in the source sequence are deleted and
+/// the elements between *a* and *b* in the target sequence are
+/// inserted (*b...a* -> *a...b*). The transposed elements are the last
+/// occurrences of *a* and *b* in the source and target sequence before
+/// the current cell, as in the algorithm of Lowrance and Wagner (1975).
+///
+/// Since the number of elements that the operation consumes depends on
+/// the sequences, this operation has an unbounded look-back.
+///
+/// Elements are transposed when they are equal according to `Eq`. Use
+/// `UnrestrictedTransposeWith` to compare elements using another
+/// equivalence.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnrestrictedTranspose<C = usize> {
+    pub transpose_cost: C,
+    pub insert_cost: C,
+    pub delete_cost: C,
+}
+
+impl<C> UnrestrictedTranspose<C>
+where
+    C: Cost,
+{
+    fn with_equal(&self) -> UnrestrictedTransposeWith<C, Equal> {
+        UnrestrictedTransposeWith {
+            transpose_cost: self.transpose_cost,
+            insert_cost: self.insert_cost,
+            delete_cost: self.delete_cost,
+            equivalence: Equal,
+        }
+    }
+}
+
+impl<T, C> Operation<T, C> for UnrestrictedTranspose<C>
+where
+    T: Eq,
+    C: Cost,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<T>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        self.with_equal()
+            .backtrack(seq_pair, source_idx, target_idx)
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<T>,
+        cost_matrix: &CostMatrix<C>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<C> {
+        self.with_equal()
+            .cost(seq_pair, cost_matrix, source_idx, target_idx)
+    }
+
+    fn has_unbounded_lookback(&self) -> bool {
+        true
+    }
+}
+
+/// Transpose operation that can span intervening edits, with an element
+/// equivalence.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct UnrestrictedTransposeWith<C, E> {
+    pub transpose_cost: C,
+    pub insert_cost: C,
+    pub delete_cost: C,
+    pub equivalence: E,
+}
+
+impl<S, U, C, E> Operation<S, C, U> for UnrestrictedTransposeWith<C, E>
+where
+    C: Cost,
+    E: Clone + Debug + Equivalence<S, U> + Eq + Hash,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        if source_idx < 2 || target_idx < 2 {
+            return None;
+        }
+
+        let a = &seq_pair.source[source_idx - 1];
+        let b = &seq_pair.target[target_idx - 1];
+
+        // Transposing equivalent elements is never cheaper than matching
+        // them.
+        if self.equivalence.equivalent(a, b) {
+            return None;
+        }
+
+        let from_source_idx = seq_pair.source[..source_idx - 1]
+            .iter()
+            .rposition(|elem| self.equivalence.equivalent(elem, b))?;
+        let from_target_idx = seq_pair.target[..target_idx - 1]
+            .iter()
+            .rposition(|elem| self.equivalence.equivalent(a, elem))?;
+
+        Some((from_source_idx, from_target_idx))
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<C> {
+        let (from_source_idx, from_target_idx) =
+            self.backtrack(seq_pair, source_idx, target_idx)?;
+
+        let n_deletes = source_idx - from_source_idx - 2;
+        let n_inserts = target_idx - from_target_idx - 2;
+
+        let cost = (0..n_deletes).fold(
+            cost_matrix[(from_source_idx, from_target_idx)].saturating_add(self.transpose_cost),
+            |cost, _| cost.saturating_add(self.delete_cost),
+        );
+        Some((0..n_inserts).fold(cost, |cost, _| cost.saturating_add(self.insert_cost)))
+    }
+
+    fn has_unbounded_lookback(&self) -> bool {
+        true
+    }
+}
+
+/// Delete operation with element-dependent cost.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DeleteWith<F>(pub F);
+
+impl<S, U, F> Operation<S, F::Cost, U> for DeleteWith<F>
+where
+    F: Clone + Debug + ElementCosts<S, U> + Eq + Hash,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        Operation::<S, F::Cost, U>::backtrack(
+            &Delete(F::Cost::zero()),
+            seq_pair,
+            source_idx,
+            target_idx,
+        )
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<F::Cost>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<F::Cost> {
+        let (from_source_idx, from_target_idx) =
+            self.backtrack(seq_pair, source_idx, target_idx)?;
+        let orig_cost = cost_matrix[(from_source_idx, from_target_idx)];
+        Some(orig_cost.saturating_add(self.0.delete_cost(&seq_pair.source[from_source_idx])))
+    }
+}
+
+/// Insert operation with element-dependent cost.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct InsertWith<F>(pub F);
+
+impl<S, U, F> Operation<S, F::Cost, U> for InsertWith<F>
+where
+    F: Clone + Debug + ElementCosts<S, U> + Eq + Hash,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        Operation::<S, F::Cost, U>::backtrack(
+            &Insert(F::Cost::zero()),
+            seq_pair,
+            source_idx,
+            target_idx,
+        )
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<F::Cost>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<F::Cost> {
+        let (from_source_idx, from_target_idx) =
+            self.backtrack(seq_pair, source_idx, target_idx)?;
+        let orig_cost = cost_matrix[(from_source_idx, from_target_idx)];
+        Some(orig_cost.saturating_add(self.0.insert_cost(&seq_pair.target[from_target_idx])))
+    }
+}
+
+/// Substitute operation with element-dependent cost.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SubstituteWith<F>(pub F);
+
+impl<S, U, F> Operation<S, F::Cost, U> for SubstituteWith<F>
+where
+    F: Clone + Debug + ElementCosts<S, U> + Eq + Hash,
+{
+    fn backtrack(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<(usize, usize)> {
+        Operation::<S, F::Cost, U>::backtrack(
+            &Substitute(F::Cost::zero()),
+            seq_pair,
+            source_idx,
+            target_idx,
+        )
+    }
+
+    fn cost(
+        &self,
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<F::Cost>,
+        source_idx: usize,
+        target_idx: usize,
+    ) -> Option<F::Cost> {
+        let (from_source_idx, from_target_idx) =
+            self.backtrack(seq_pair, source_idx, target_idx)?;
+        let orig_cost = cost_matrix[(from_source_idx, from_target_idx)];
+        Some(orig_cost.saturating_add(self.0.substitute_cost(
+            &seq_pair.source[from_source_idx],
+            &seq_pair.target[from_target_idx],
+        )))
+    }
+}
@@ -4,12 +4,19 @@ use std::cmp;
 use std::fmt::Debug;
 use std::hash::Hash;
 
-use crate::{Measure, SeqPair};
+use crate::cost::Cost;
+use crate::{CostMatrix, Measure, SeqPair};
 
 pub mod archetype;
 
 /// Trait for sequence edit operations.
-pub trait Operation<T>: Clone + Debug + Eq + Hash {
+///
+/// The type parameter `C` is the type of the operation's cost. `S` and
+/// `U` are the element types of the source and target sequences.
+pub trait Operation<S, C = usize, U = S>: Clone + Debug + Eq + Hash
+where
+    C: Cost,
+{
     /// Return the cell after backtracking from the given cell with this operation.
     ///
     /// Must return `None` if backtracking is not possible (e.g. would lead
@@ -17,7 +24,7 @@ pub trait Operation<T>: Clone + Debug + Eq + Hash {
     /// traces and edit scripts.
     fn backtrack(
         &self,
-        seq_pair: &SeqPair<T>,
+        seq_pair: &SeqPair<S, U>,
         source_idx: usize,
         target_idx: usize,
     ) -> Option<(usize, usize)>;
@@ -26,16 +33,29 @@ pub trait Operation<T>: Clone + Debug + Eq + Hash {
     ///
     /// Returns `None` if the operation cannot be applied. Otherwise, it
     /// returns the cost for the alignment at `source_idx`, `target_idx`
-    /// using this operation.
+    /// using this operation. Costs should be added with
+    /// `Cost::saturating_add`.
     fn cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<C>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq;
+    ) -> Option<C>;
+
+    /// Check whether the operation can backtrack over an unbounded
+    /// number of rows or columns.
+    ///
+    /// The number of rows and columns that operations backtrack over is
+    /// normally determined by backtracking from the last cell of the cost
+    /// matrix. This is used to only store the last rows of the cost
+    /// matrix and to restrict `Align::align_within` to a band. Operations
+    /// where the number of elements that are consumed depends on the
+    /// sequences must return `true`, so that the full cost matrix is
+    /// used instead.
+    fn has_unbounded_lookback(&self) -> bool {
+        false
+    }
 }
 
 ///An indexed edit operation.
@@ -87,51 +107,54 @@ where
     }
 }
 
-pub(crate) trait Backtrack<T> {
-    type Operation: Operation<T>;
+/// An edit script.
+///
+/// An edit script is the sequence of indexed operations that rewrites the
+/// source sequence to the target sequence.
+pub type EditScript<O> = Vec<IndexedOperation<O>>;
+
+pub(crate) trait Backtrack<S, U = S> {
+    type Cost: Cost;
+
+    type Operation: Operation<S, Self::Cost, U>;
 
     fn backtrack(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<Self::Cost>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<Self::Operation>
-    where
-        T: Eq;
+    ) -> Option<Self::Operation>;
 
     fn backtracks(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<Self::Cost>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Vec<Self::Operation>
-    where
-        T: Eq;
+    ) -> Vec<Self::Operation>;
 }
 
-impl<M, T> Backtrack<T> for M
+impl<M, S, U> Backtrack<S, U> for M
 where
-    M: Measure<T>,
+    M: Measure<S, U>,
 {
+    type Cost = M::Cost;
+
     type Operation = M::Operation;
 
     /// Give the operation that was used to construct the cost matrix cell
     /// at (`source_idx`, `target_idx`).
     fn backtrack(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<Self::Cost>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<Self::Operation>
-    where
-        T: Eq,
-    {
+    ) -> Option<Self::Operation> {
         for op in self.operations() {
             if let Some(cost) = op.cost(seq_pair, cost_matrix, source_idx, target_idx) {
-                if cost == cost_matrix[source_idx][target_idx] {
+                if cost == cost_matrix[(source_idx, target_idx)] {
                     return Some(op.clone());
                 }
             }
@@ -144,19 +167,16 @@ where
     /// at (`source_idx`, `taget_idx`).
     fn backtracks(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<Self::Cost>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Vec<Self::Operation>
-    where
-        T: Eq,
-    {
+    ) -> Vec<Self::Operation> {
         let mut ops = Vec::new();
 
         for op in self.operations() {
             if let Some(cost) = op.cost(seq_pair, cost_matrix, source_idx, target_idx) {
-                if cost == cost_matrix[source_idx][target_idx] {
+                if cost == cost_matrix[(source_idx, target_idx)] {
                     ops.push(op.clone());
                 }
             }
@@ -166,24 +186,26 @@ where
     }
 }
 
-pub(crate) trait BestCost<T> {
-    type Operation: Operation<T>;
+pub(crate) trait BestCost<S, U = S> {
+    type Cost: Cost;
+
+    type Operation: Operation<S, Self::Cost, U>;
 
     fn best_cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<Self::Cost>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq;
+    ) -> Option<Self::Cost>;
 }
 
-impl<M, T> BestCost<T> for M
+impl<M, S, U> BestCost<S, U> for M
 where
-    M: Measure<T>,
+    M: Measure<S, U>,
 {
+    type Cost = M::Cost;
+
     type Operation = M::Operation;
 
     /// Compute the cost of the best operation.
@@ -193,14 +215,11 @@ where
     /// using this operation.
     fn best_cost(
         &self,
-        seq_pair: &SeqPair<T>,
-        cost_matrix: &[Vec<usize>],
+        seq_pair: &SeqPair<S, U>,
+        cost_matrix: &CostMatrix<Self::Cost>,
         source_idx: usize,
         target_idx: usize,
-    ) -> Option<usize>
-    where
-        T: Eq,
-    {
+    ) -> Option<Self::Cost> {
         // Used filter_map + min_by_key before, but the compiler is not able
         // to optimize to a better loop.
         let mut best = None;
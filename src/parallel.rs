@@ -0,0 +1,276 @@
+//! Parallel alignment.
+//!
+//! This module provides functions that distribute the alignment of many
+//! sequences over multiple threads using rayon. It is only available when
+//! the `rayon` feature is enabled.
+
+use rayon::prelude::*;
+
+use crate::cost::Cost;
+use crate::{Align, Aligner, AlignmentResult, Measure};
+
+/// Pairwise distance matrix.
+///
+/// The distances are stored in condensed form: the distances above the
+/// diagonal in row-major order. For a symmetric measure, this is the full
+/// distance matrix. For other measures, the distances below the diagonal
+/// are stored separately. The distances of sequences to themselves are
+/// stored as well, since they are not zero for every measure.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DistanceMatrix<C = usize> {
+    len: usize,
+    diagonal: Vec<C>,
+    upper: Vec<C>,
+    lower: Option<Vec<C>>,
+}
+
+impl<C> DistanceMatrix<C>
+where
+    C: Cost,
+{
+    /// Get the condensed distance matrix.
+    ///
+    /// Returns the distances above the diagonal in row-major order, so
+    /// that the distance between sequences *i* and *j* (*i < j*) is at
+    /// index *len · i - i · (i + 1) / 2 + j - i - 1*. The distances of
+    /// sequences to themselves are not included, see `diagonal`. Returns
+    /// `None` if the matrix is not symmetric.
+    pub fn condensed(&self) -> Option<&[C]> {
+        match self.lower {
+            Some(_) => None,
+            None => Some(&self.upper),
+        }
+    }
+
+    /// Get the distances of the sequences to themselves.
+    pub fn diagonal(&self) -> &[C] {
+        &self.diagonal
+    }
+
+    /// Get the distance from sequence `source` to sequence `target`.
+    pub fn distance(&self, source: usize, target: usize) -> C {
+        assert!(
+            source < self.len && target < self.len,
+            "Index ({}, {}) out of bounds for distance matrix of {} sequences",
+            source,
+            target,
+            self.len
+        );
+
+        if source < target {
+            self.upper[condensed_index(self.len, source, target)]
+        } else if source > target {
+            let distances = self.lower.as_ref().unwrap_or(&self.upper);
+            distances[condensed_index(self.len, target, source)]
+        } else {
+            self.diagonal[source]
+        }
+    }
+
+    /// Check whether the distance matrix is symmetric.
+    pub fn is_symmetric(&self) -> bool {
+        self.lower.is_none()
+    }
+
+    /// Get the number of sequences.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Check whether the distance matrix is for zero sequences.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+fn condensed_index(len: usize, row: usize, column: usize) -> usize {
+    len * row - row * (row + 1) / 2 + column - row - 1
+}
+
+/// Compute the distances between all pairs of sequences in parallel.
+///
+/// If the measure is symmetric (see `Measure::is_symmetric`), the
+/// distance of every pair is only computed once. Otherwise, the distances
+/// are computed in both directions. The distances of the
+/// sequences to themselves are also computed.
+///
+/// ```
+/// use seqalign::measures::Levenshtein;
+/// use seqalign::parallel::pairwise_distances;
+///
+/// let words: Vec<Vec<char>> = ["pineapple", "pen", "apple"]
+///     .iter()
+///     .map(|word| word.chars().collect())
+///     .collect();
+/// let words: Vec<&[char]> = words.iter().map(Vec::as_slice).collect();
+///
+/// let distances = pairwise_distances(&Levenshtein::new(1, 1, 1), &words);
+/// assert_eq!(distances.condensed(), Some(&[7, 4, 4][..]));
+/// assert_eq!(distances.distance(2, 0), 4);
+/// ```
+pub fn pairwise_distances<M, T>(measure: &M, sequences: &[&[T]]) -> DistanceMatrix<M::Cost>
+where
+    M: Measure<T> + Sync,
+    M::Cost: Send,
+    T: Sync,
+{
+    let len = sequences.len();
+
+    let diagonal = sequences
+        .par_iter()
+        .map(|sequence| measure.distance(sequence, sequence))
+        .collect();
+
+    let upper = upper_distances(len, |row, column| {
+        measure.distance(sequences[row], sequences[column])
+    });
+
+    let lower = if measure.is_symmetric() {
+        None
+    } else {
+        Some(upper_distances(len, |row, column| {
+            measure.distance(sequences[column], sequences[row])
+        }))
+    };
+
+    DistanceMatrix {
+        len,
+        diagonal,
+        upper,
+        lower,
+    }
+}
+
+fn upper_distances<C, F>(len: usize, distance: F) -> Vec<C>
+where
+    C: Send,
+    F: Fn(usize, usize) -> C + Sync,
+{
+    // Rows have different lengths, work stealing takes care of balancing
+    // the load between threads.
+    (0..len)
+        .into_par_iter()
+        .flat_map_iter(|row| (row + 1..len).map(move |column| (row, column)))
+        .map(|(row, column)| distance(row, column))
+        .collect()
+}
+
+/// Align many pairs of sequences in parallel.
+///
+/// Every thread uses its own `Aligner`, so that the cost matrix buffer is
+/// reused between the pairs that are aligned by that thread. The results
+/// are in the same order as the pairs.
+///
+/// Panics when none of the measure's operations can be applied to a cell
+/// of the cost matrix or when it is not possible to backtrack.
+pub fn align_many<M, T>(
+    measure: &M,
+    pairs: &[(&[T], &[T])],
+) -> Vec<AlignmentResult<M::Operation, M::Cost>>
+where
+    M: Clone + Measure<T> + Send + Sync,
+    M::Cost: Send,
+    M::Operation: Send,
+    T: Sync,
+{
+    pairs
+        .par_iter()
+        .map_init(
+            || Aligner::new(measure.clone()),
+            |aligner, &(source, target)| aligner.align(source, target),
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::measures::{Levenshtein, LevenshteinDamerau, MatrixLevenshtein, LCS};
+    use crate::{Align, Measure, SubstitutionMatrix};
+
+    use super::{align_many, pairwise_distances};
+
+    static WORDS: &[&str] = &["pineapple", "", "pen", "apple", "applet", "tpyo", "typo"];
+
+    fn words() -> Vec<Vec<char>> {
+        WORDS.iter().map(|word| word.chars().collect()).collect()
+    }
+
+    fn check_pairwise_distances<M>(measure: M, symmetric: bool)
+    where
+        M: Measure<char, Cost = usize> + Sync,
+    {
+        let words = words();
+        let words: Vec<&[char]> = words.iter().map(Vec::as_slice).collect();
+
+        let distances = pairwise_distances(&measure, &words);
+        assert_eq!(distances.len(), words.len());
+        assert_eq!(distances.is_symmetric(), symmetric);
+        assert_eq!(distances.condensed().is_some(), symmetric);
+
+        for (i, source) in words.iter().enumerate() {
+            for (j, target) in words.iter().enumerate() {
+                assert_eq!(distances.distance(i, j), measure.distance(source, target));
+            }
+        }
+    }
+
+    #[test]
+    fn pairwise_distances_test() {
+        check_pairwise_distances(Levenshtein::new(1, 1, 1), true);
+        check_pairwise_distances(Levenshtein::new(1, 1, 3), true);
+        check_pairwise_distances(LevenshteinDamerau::new(1, 1, 1, 1), true);
+        check_pairwise_distances(LCS::new(1, 2), false);
+    }
+
+    #[test]
+    fn pairwise_distances_diagonal_test() {
+        // Matches have a cost with a substitution matrix, so the distance
+        // of a sequence to itself is not zero.
+        let words: Vec<Vec<char>> = ["PINEAPPLE", "PEN", "APPLE"]
+            .iter()
+            .map(|word| word.chars().collect())
+            .collect();
+        let words: Vec<&[char]> = words.iter().map(Vec::as_slice).collect();
+
+        let measure = MatrixLevenshtein::new(SubstitutionMatrix::blosum62(), 4, 4);
+        let distances = pairwise_distances(&measure, &words);
+        assert!(distances.is_symmetric());
+
+        for (i, word) in words.iter().enumerate() {
+            let distance = measure.distance(word, word);
+            assert!(distance > 0);
+            assert_eq!(distances.diagonal()[i], distance);
+            assert_eq!(distances.distance(i, i), distance);
+        }
+    }
+
+    #[test]
+    fn pairwise_distances_condensed_test() {
+        let words = words();
+        let words: Vec<&[char]> = words[..4].iter().map(Vec::as_slice).collect();
+
+        let distances = pairwise_distances(&Levenshtein::new(1, 1, 1), &words);
+        assert_eq!(distances.condensed(), Some(&[9, 7, 4, 3, 5, 4][..]));
+
+        assert!(pairwise_distances(&Levenshtein::new(1, 1, 1), &words[..0]).is_empty());
+    }
+
+    #[test]
+    fn align_many_test() {
+        let words = words();
+        let pairs: Vec<(&[char], &[char])> = words
+            .iter()
+            .flat_map(|source| words.iter().map(move |target| (&source[..], &target[..])))
+            .collect();
+
+        let measure = LevenshteinDamerau::new(1, 1, 1, 1);
+        let results = align_many(&measure, &pairs);
+        assert_eq!(results.len(), pairs.len());
+
+        for (result, (source, target)) in results.iter().zip(pairs) {
+            let alignment = measure.align(source, target);
+            assert_eq!(result.distance(), alignment.distance());
+            assert_eq!(result.edit_script(), alignment.edit_script().as_slice());
+        }
+    }
+}
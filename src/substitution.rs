@@ -0,0 +1,332 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::str::FromStr;
+
+use crate::AlignError;
+
+/// Substitution matrix errors.
+///
+/// These errors are returned when reading or parsing a substitution
+/// matrix. Looking up a symbol that is not in the matrix returns
+/// `AlignError::UnknownSymbol`.
+#[derive(Debug)]
+pub enum SubstitutionMatrixError {
+    /// An I/O error occurred while reading a substitution matrix.
+    Io(io::Error),
+
+    /// A line of a substitution matrix could not be parsed.
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for SubstitutionMatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::SubstitutionMatrixError::*;
+
+        match self {
+            Io(err) => write!(f, "Cannot read substitution matrix: {}", err),
+            Syntax { line, message } => {
+                write!(
+                    f,
+                    "Invalid substitution matrix on line {}: {}",
+                    line, message
+                )
+            }
+        }
+    }
+}
+
+impl Error for SubstitutionMatrixError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SubstitutionMatrixError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SubstitutionMatrixError {
+    fn from(err: io::Error) -> Self {
+        SubstitutionMatrixError::Io(err)
+    }
+}
+
+/// Substitution matrix.
+///
+/// A substitution matrix gives a similarity score for every pair of
+/// symbols, such as the BLOSUM and PAM matrices for amino acids.
+/// Matrices are read from the whitespace-separated format that is used
+/// by NCBI:
+///
+/// ```text
+/// # Comment
+///    A  R  N
+/// A  4 -1 -2
+/// R -1  5  0
+/// N -2  0  6
+/// ```
+///
+/// Symbols are case-sensitive.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct SubstitutionMatrix {
+    symbols: Vec<char>,
+    sorted_symbols: Vec<(char, usize)>,
+    scores: Vec<i32>,
+    max_score: i32,
+}
+
+impl SubstitutionMatrix {
+    /// Get the BLOSUM62 substitution matrix.
+    pub fn blosum62() -> Self {
+        include_str!("matrices/BLOSUM62")
+            .parse()
+            .expect("Invalid built-in BLOSUM62 matrix")
+    }
+
+    /// Get the PAM250 substitution matrix.
+    pub fn pam250() -> Self {
+        include_str!("matrices/PAM250")
+            .parse()
+            .expect("Invalid built-in PAM250 matrix")
+    }
+
+    /// Read a substitution matrix in NCBI format.
+    pub fn read<R>(mut read: R) -> Result<Self, SubstitutionMatrixError>
+    where
+        R: Read,
+    {
+        let mut data = String::new();
+        read.read_to_string(&mut data)?;
+        data.parse()
+    }
+
+    /// Check that all symbols of a sequence are in the substitution
+    /// matrix.
+    ///
+    /// Returns `AlignError::UnknownSymbol` for the first symbol that is
+    /// not in the matrix.
+    pub fn check_symbols(&self, sequence: &[char]) -> Result<(), AlignError> {
+        match sequence
+            .iter()
+            .find(|&&symbol| self.index(symbol).is_none())
+        {
+            Some(&symbol) => Err(AlignError::UnknownSymbol { symbol }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check whether the substitution matrix is symmetric.
+    pub fn is_symmetric(&self) -> bool {
+        let n_symbols = self.symbols.len();
+        (0..n_symbols).all(|row| {
+            (0..row).all(|column| {
+                self.scores[row * n_symbols + column] == self.scores[column * n_symbols + row]
+            })
+        })
+    }
+
+    /// Get the highest score in the matrix.
+    pub fn max_score(&self) -> i32 {
+        self.max_score
+    }
+
+    /// Get the score of substituting `source` by `target`.
+    ///
+    /// Returns `AlignError::UnknownSymbol` when `source` or `target` is
+    /// not in the matrix.
+    pub fn score(&self, source: char, target: char) -> Result<i32, AlignError> {
+        let source_idx = self
+            .index(source)
+            .ok_or(AlignError::UnknownSymbol { symbol: source })?;
+        let target_idx = self
+            .index(target)
+            .ok_or(AlignError::UnknownSymbol { symbol: target })?;
+        Ok(self.scores[source_idx * self.symbols.len() + target_idx])
+    }
+
+    /// Get the symbols of the matrix.
+    pub fn symbols(&self) -> &[char] {
+        &self.symbols
+    }
+
+    fn index(&self, symbol: char) -> Option<usize> {
+        self.sorted_symbols
+            .binary_search_by_key(&symbol, |&(symbol, _)| symbol)
+            .ok()
+            .map(|idx| self.sorted_symbols[idx].1)
+    }
+}
+
+impl FromStr for SubstitutionMatrix {
+    type Err = SubstitutionMatrixError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let syntax_error = |line: usize, message: String| SubstitutionMatrixError::Syntax {
+            line: line + 1,
+            message,
+        };
+
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+
+        let (header_line, header) = lines
+            .next()
+            .ok_or_else(|| syntax_error(0, "matrix is empty".to_string()))?;
+        let symbols = header
+            .split_whitespace()
+            .map(|token| parse_symbol(token).ok_or_else(|| token.to_string()))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|token| syntax_error(header_line, format!("invalid symbol '{}'", token)))?;
+
+        let mut sorted_symbols: Vec<_> = symbols
+            .iter()
+            .enumerate()
+            .map(|(idx, &symbol)| (symbol, idx))
+            .collect();
+        sorted_symbols.sort_unstable();
+        if let Some(window) = sorted_symbols.windows(2).find(|w| w[0].0 == w[1].0) {
+            return Err(syntax_error(
+                header_line,
+                format!("duplicate symbol '{}'", window[0].0),
+            ));
+        }
+
+        let n_symbols = symbols.len();
+        let mut scores = vec![0; n_symbols * n_symbols];
+        let mut seen = vec![false; n_symbols];
+        for (line_idx, line) in lines {
+            let mut tokens = line.split_whitespace();
+
+            let token = tokens.next().expect("Empty lines are skipped");
+            let row = parse_symbol(token)
+                .and_then(|symbol| {
+                    sorted_symbols
+                        .binary_search_by_key(&symbol, |&(symbol, _)| symbol)
+                        .ok()
+                })
+                .map(|idx| sorted_symbols[idx].1)
+                .ok_or_else(|| syntax_error(line_idx, format!("unknown row symbol '{}'", token)))?;
+            if seen[row] {
+                return Err(syntax_error(
+                    line_idx,
+                    format!("duplicate row for symbol '{}'", symbols[row]),
+                ));
+            }
+            seen[row] = true;
+
+            let row_scores = tokens
+                .map(|token| token.parse::<i32>().map_err(|_| token))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|token| syntax_error(line_idx, format!("invalid score '{}'", token)))?;
+            if row_scores.len() != n_symbols {
+                return Err(syntax_error(
+                    line_idx,
+                    format!("expected {} scores, found {}", n_symbols, row_scores.len()),
+                ));
+            }
+
+            scores[row * n_symbols..(row + 1) * n_symbols].copy_from_slice(&row_scores);
+        }
+
+        if let Some(row) = seen.iter().position(|&seen| !seen) {
+            return Err(syntax_error(
+                header_line,
+                format!("missing row for symbol '{}'", symbols[row]),
+            ));
+        }
+
+        Ok(SubstitutionMatrix {
+            max_score: scores.iter().cloned().max().unwrap_or(0),
+            symbols,
+            sorted_symbols,
+            scores,
+        })
+    }
+}
+
+fn parse_symbol(token: &str) -> Option<char> {
+    let mut chars = token.chars();
+    match (chars.next(), chars.next()) {
+        (Some(symbol), None) => Some(symbol),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SubstitutionMatrix, SubstitutionMatrixError};
+    use crate::AlignError;
+
+    #[test]
+    fn builtin_matrices_test() {
+        let blosum62 = SubstitutionMatrix::blosum62();
+        assert_eq!(blosum62.symbols().len(), 24);
+        assert!(blosum62.is_symmetric());
+        assert_eq!(blosum62.max_score(), 11);
+        assert_eq!(blosum62.score('A', 'A').unwrap(), 4);
+        assert_eq!(blosum62.score('W', 'F').unwrap(), 1);
+        assert_eq!(blosum62.score('D', 'L').unwrap(), -4);
+        assert_eq!(blosum62.score('*', '*').unwrap(), 1);
+
+        let pam250 = SubstitutionMatrix::pam250();
+        assert_eq!(pam250.symbols().len(), 24);
+        assert!(pam250.is_symmetric());
+        assert_eq!(pam250.max_score(), 17);
+        assert_eq!(pam250.score('C', 'C').unwrap(), 12);
+        assert_eq!(pam250.score('F', 'Y').unwrap(), 7);
+        assert_eq!(pam250.score('W', 'C').unwrap(), -8);
+    }
+
+    #[test]
+    fn parse_test() {
+        let matrix: SubstitutionMatrix =
+            "# Comment\n\n   a  b\na  2 -1\nb -3  1\n".parse().unwrap();
+        assert_eq!(matrix.symbols(), &['a', 'b']);
+        assert!(!matrix.is_symmetric());
+        assert_eq!(matrix.score('a', 'b').unwrap(), -1);
+        assert_eq!(matrix.score('b', 'a').unwrap(), -3);
+
+        let read = SubstitutionMatrix::read("   a  b\nb -3  1\na  2 -1\n".as_bytes()).unwrap();
+        assert_eq!(read, matrix);
+    }
+
+    #[test]
+    fn parse_error_test() {
+        let check_syntax_error = |data: &str, expected_line: usize| match data
+            .parse::<SubstitutionMatrix>()
+        {
+            Err(SubstitutionMatrixError::Syntax { line, .. }) => assert_eq!(line, expected_line),
+            result => panic!("Expected syntax error, got: {:?}", result),
+        };
+
+        check_syntax_error("", 1);
+        check_syntax_error("  ab c\na 1 2\n", 1);
+        check_syntax_error("  a a\na 1 2\n", 1);
+        check_syntax_error("  a b\na 1 2\nb 3\n", 3);
+        check_syntax_error("  a b\na 1 x\nb 3 4\n", 2);
+        check_syntax_error("  a b\na 1 2\nc 3 4\n", 3);
+        check_syntax_error("  a b\na 1 2\na 3 4\n", 3);
+        check_syntax_error("  a b\na 1 2\n", 1);
+    }
+
+    #[test]
+    fn unknown_symbol_test() {
+        let blosum62 = SubstitutionMatrix::blosum62();
+
+        let err = blosum62.score('A', 'J').unwrap_err();
+        assert_eq!(err, AlignError::UnknownSymbol { symbol: 'J' });
+        assert_eq!(
+            err.to_string(),
+            "Symbol 'J' is not in the substitution matrix"
+        );
+
+        assert!(blosum62.check_symbols(&['A', 'R', 'N']).is_ok());
+        assert_eq!(
+            blosum62.check_symbols(&['A', 'r', 'N']),
+            Err(AlignError::UnknownSymbol { symbol: 'r' })
+        );
+    }
+}